@@ -0,0 +1,182 @@
+//! The board, reduced to the places a token can stand.
+
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::{Room, Suspect};
+
+/// Where a token stands. Serialized as the bare room or square name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Position {
+    Room(Room),
+    Square(Square),
+}
+
+/// The named squares on the board's edge where the suspects start.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) enum Square {
+    /// Bottom edge, between the Lounge and the Hall.
+    ScarlettStart,
+    /// Left edge, between the Lounge and the Dining Room.
+    MustardStart,
+    /// Top edge, left of the Ballroom.
+    OrchidStart,
+    /// Top edge, right of the Ballroom.
+    GreenStart,
+    /// Right edge, beside the Conservatory.
+    PeacockStart,
+    /// Right edge, beside the Study.
+    PlumStart,
+}
+
+impl Square {
+    pub(crate) fn start_of(suspect: &Suspect) -> Self {
+        match suspect {
+            Suspect::Scarlett => Square::ScarlettStart,
+            Suspect::Mustard => Square::MustardStart,
+            Suspect::Orchid => Square::OrchidStart,
+            Suspect::Green => Square::GreenStart,
+            Suspect::Peacock => Square::PeacockStart,
+            Suspect::Plum => Square::PlumStart,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct StartingSquare {
+    suspect: Suspect,
+    square: Square,
+}
+
+/// A secret passage between two rooms, usable in either direction.
+pub(crate) type Passage = (Room, Room);
+
+/// The two diagonals of the classic board.
+pub(crate) fn classic_passages() -> Vec<Passage> {
+    vec![
+        (Room::Kitchen, Room::Study),
+        (Room::Lounge, Room::Conservatory),
+    ]
+}
+
+pub(crate) fn connects(passages: &[Passage], from: &Room, to: &Room) -> bool {
+    passages
+        .iter()
+        .any(|(a, b)| (a == from && b == to) || (a == to && b == from))
+}
+
+/// Passages that lead nowhere or repeat another, in either direction.
+pub(crate) fn passage_problems(passages: &[Passage]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (index, (a, b)) in passages.iter().enumerate() {
+        if a == b {
+            problems.push(format!("passage from {a:?} leads back to itself"));
+        } else if connects(&passages[..index], a, b) {
+            problems.push(format!("passage between {a:?} and {b:?} given twice"));
+        }
+    }
+
+    problems
+}
+
+/// Corridor steps between the doors of two rooms on the classic board, the
+/// shortest way round and ignoring secret passages. Each pair appears once.
+const CORRIDOR_STEPS: [(Room, Room, u32); 36] = [
+    (Room::Kitchen, Room::Ballroom, 7),
+    (Room::Kitchen, Room::Conservatory, 20),
+    (Room::Kitchen, Room::BilliardRoom, 17),
+    (Room::Kitchen, Room::Library, 23),
+    (Room::Kitchen, Room::Study, 28),
+    (Room::Kitchen, Room::Hall, 20),
+    (Room::Kitchen, Room::Lounge, 19),
+    (Room::Kitchen, Room::DiningRoom, 11),
+    (Room::Ballroom, Room::Conservatory, 4),
+    (Room::Ballroom, Room::BilliardRoom, 6),
+    (Room::Ballroom, Room::Library, 12),
+    (Room::Ballroom, Room::Study, 17),
+    (Room::Ballroom, Room::Hall, 13),
+    (Room::Ballroom, Room::Lounge, 13),
+    (Room::Ballroom, Room::DiningRoom, 7),
+    (Room::Conservatory, Room::BilliardRoom, 7),
+    (Room::Conservatory, Room::Library, 14),
+    (Room::Conservatory, Room::Study, 20),
+    (Room::Conservatory, Room::Hall, 17),
+    (Room::Conservatory, Room::Lounge, 26),
+    (Room::Conservatory, Room::DiningRoom, 19),
+    (Room::BilliardRoom, Room::Library, 4),
+    (Room::BilliardRoom, Room::Study, 15),
+    (Room::BilliardRoom, Room::Hall, 15),
+    (Room::BilliardRoom, Room::Lounge, 19),
+    (Room::BilliardRoom, Room::DiningRoom, 14),
+    (Room::Library, Room::Study, 7),
+    (Room::Library, Room::Hall, 7),
+    (Room::Library, Room::Lounge, 14),
+    (Room::Library, Room::DiningRoom, 14),
+    (Room::Study, Room::Hall, 4),
+    (Room::Study, Room::Lounge, 17),
+    (Room::Study, Room::DiningRoom, 17),
+    (Room::Hall, Room::Lounge, 8),
+    (Room::Hall, Room::DiningRoom, 8),
+    (Room::Lounge, Room::DiningRoom, 4),
+];
+
+/// The fewest steps from one room to another, walking the corridors or
+/// taking a secret passage at `passage_steps` a time.
+pub(crate) fn distance(passages: &[Passage], passage_steps: u32, from: &Room, to: &Room) -> u32 {
+    let rooms: Vec<Room> = Room::iter().collect();
+    let at = |room: &Room| rooms.iter().position(|r| r == room).unwrap();
+
+    let mut steps = vec![vec![u32::MAX; rooms.len()]; rooms.len()];
+    for (index, row) in steps.iter_mut().enumerate() {
+        row[index] = 0;
+    }
+    for (a, b, corridor) in &CORRIDOR_STEPS {
+        steps[at(a)][at(b)] = *corridor;
+        steps[at(b)][at(a)] = *corridor;
+    }
+    for (a, b) in passages {
+        let (a, b) = (at(a), at(b));
+        steps[a][b] = steps[a][b].min(passage_steps);
+        steps[b][a] = steps[b][a].min(passage_steps);
+    }
+
+    // Nine rooms: Floyd-Warshall is plenty.
+    for via in 0..rooms.len() {
+        for from in 0..rooms.len() {
+            for to in 0..rooms.len() {
+                let through = steps[from][via].saturating_add(steps[via][to]);
+                if through < steps[from][to] {
+                    steps[from][to] = through;
+                }
+            }
+        }
+    }
+
+    steps[at(from)][at(to)]
+}
+
+#[derive(Serialize)]
+pub(crate) struct Layout {
+    rooms: Vec<Room>,
+    starting_squares: Vec<StartingSquare>,
+    secret_passages: Vec<Passage>,
+}
+
+impl Layout {
+    /// The classic board with a game's own `secret_passages`.
+    pub(crate) fn with_passages(secret_passages: &[Passage]) -> Self {
+        Self {
+            rooms: Room::iter().collect(),
+            starting_squares: Suspect::iter()
+                .map(|suspect| StartingSquare {
+                    square: Square::start_of(&suspect),
+                    suspect,
+                })
+                .collect(),
+            secret_passages: secret_passages.to_vec(),
+        }
+    }
+}
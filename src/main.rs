@@ -1,24 +1,68 @@
-use std::sync::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::{Deref, DerefMut},
+    str::FromStr,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError, TryLockError,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use rand::{
-    rng,
+    Rng, RngCore, SeedableRng, rng,
+    rngs::StdRng,
     seq::{IndexedRandom, SliceRandom},
 };
 use rocket::{
-    State,
-    http::{ContentType, Status},
+    Build, Data, Either, Request, Response, Rocket, State,
+    fairing::{Fairing, Info, Kind},
+    figment::Figment,
+    form::{self, FromFormField, ValueField},
+    http::{ContentType, Method, Status},
+    request::{FromRequest, Outcome},
+    response::{self, Responder},
     serde::json::Json,
 };
 use serde::{Deserialize, Serialize};
-use serde_json::to_string;
-use strum::{EnumIter, IntoEnumIterator};
+use serde_json::{Map, Value, json, to_string, to_value};
+use strum::{EnumIter, EnumString, IntoEnumIterator};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use board::{Layout, Passage, Position, Square};
+
+mod board;
+#[cfg(test)]
+mod tests;
 
 #[macro_use]
 extern crate rocket;
 
 #[launch]
 fn rocket() -> _ {
-    rocket::build()
+    server(rocket::build())
+}
+
+/// The whole server, configured from `rocket`'s figment.
+fn server(rocket: Rocket<Build>) -> Rocket<Build> {
+    let dealer = configured_dealer(rocket.figment());
+    let admin = AdminConfig {
+        token: rocket.figment().extract_inner("admin_token").ok(),
+        reveal_on_delete: rocket
+            .figment()
+            .extract_inner("reveal_on_delete")
+            .unwrap_or(false),
+    };
+    let timeout = Duration::from_millis(
+        rocket
+            .figment()
+            .extract_inner("request_timeout_ms")
+            .unwrap_or(2000),
+    );
+    let audit = AuditLog::default();
+
+    rocket
         .mount(
             "/",
             routes![
@@ -27,246 +71,5539 @@ fn rocket() -> _ {
                 get_players,
                 get_player,
                 create_game,
+                get_game,
                 delete_game,
-                suggest
+                transfer_host,
+                kick_player,
+                pause_game,
+                resume_game,
+                suggest,
+                get_history,
+                get_suggestions_by,
+                move_player,
+                end_turn,
+                get_moves,
+                accuse,
+                get_eliminated,
+                get_presets,
+                is_solution,
+                set_color,
+                export_state,
+                import_state,
+                get_notebook,
+                mark_notebook,
+                get_board,
+                practice_hypothetical,
+                get_available_actions,
+                reveal,
+                resign,
+                get_unused_cards,
+                get_probability,
+                get_stats,
+                update_settings,
+                preview_deal,
+                get_passes,
+                get_turn_order,
+                get_unseen_count,
+                get_summary,
+                roll,
+                get_remaining_steps,
+                rematch,
+                get_cards,
+                validate_config,
+                play_intrigue,
+                get_visited_rooms,
+                get_audit,
+                force_next_turn,
+                take_turn,
+                simulate_deal,
+                get_card_order,
+                export_notebook,
+                get_config,
+                get_pending_disproofs,
+                get_winner,
+                get_rules,
+                get_suggestions_in,
+                get_solved,
+                get_known_absences,
+                get_daily,
+                daily_suggest,
+                daily_accuse,
+                get_events_since,
+                get_distance,
+                get_winnable,
+                get_editions,
+                register_edition,
+                no_suggestion,
+                get_suspect_tokens,
+                get_candidate_counts,
+                delete_edition
             ],
         )
-        .manage(Mutex::new(GameState::new()))
+        .register(
+            "/",
+            catchers![not_found, unprocessable, internal_error, default_catcher],
+        )
+        .manage(SharedGame {
+            state: Mutex::new(GameState::new()),
+            timeout,
+            version: AtomicU64::new(0),
+            archive: Mutex::default(),
+            audit: audit.clone(),
+        })
+        .manage(dealer)
+        .manage(audit)
+        .manage(ViewCache::default())
+        .manage(Editions::default())
+        .manage(admin)
+        .attach(RequestTimer { timeout })
     // .manage(Won { 0: -1 })
 }
 
-#[derive(Debug, Serialize)]
-struct GameState {
-    players: Vec<Player>,
-    solution: Option<Suggestion>,
+/// The game every request works on. Waiting for the lock gives up after
+/// `timeout`, so one slow request can't queue up every other one.
+struct SharedGame {
+    state: Mutex<GameState>,
+    timeout: Duration,
+    /// Bumped on every change to the game. Kept out of `GameState` so an
+    /// import can't wind it back onto a version something already cached.
+    version: AtomicU64,
+    /// Every game that has finished since the server started, for `/stats`.
+    archive: Mutex<Vec<FinishedGame>>,
+    /// For what `tick` does without anyone asking.
+    audit: AuditLog,
 }
 
-impl GameState {
-    fn new() -> Self {
-        GameState {
-            players: Vec::new(),
-            solution: None,
+impl SharedGame {
+    /// Locks the game, first applying anything that came due since the last
+    /// request, such as an expired turn timer.
+    fn lock(&self) -> Result<GameGuard<'_>, ApiError> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            let mut guard = match self.state.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(poisoned)) => {
+                    // A handler panicked mid-request; carry on with whatever it left.
+                    self.state.clear_poison();
+                    poisoned.into_inner()
+                }
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(1));
+                    continue;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(ApiError::new(
+                        Status::ServiceUnavailable,
+                        "game is busy, try again",
+                    )
+                    .with_retry_after(1));
+                }
+            };
+
+            let was_finished = guard.phase == Phase::Finished;
+            if guard.tick(now_secs(), &self.audit) {
+                self.version.fetch_add(1, Ordering::Relaxed);
+            }
+            return Ok(GameGuard {
+                state: guard,
+                version: &self.version,
+                archive: &self.archive,
+                was_finished,
+            });
         }
     }
+
+    fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// A copy of the game and the version it was taken at. Reads that
+    /// serialize a lot work on this, so writers only wait for the copy.
+    fn snapshot(&self) -> Result<(GameState, u64), ApiError> {
+        let guard = self.lock()?;
+        Ok((GameState::clone(&guard), self.version()))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Suggestion {
-    suspect: Suspect,
-    weapon: Weapon,
-    room: Room,
+/// The locked game. Reading goes straight through; mutable access counts as
+/// a change and bumps the version. A game that finishes while locked is
+/// archived on unlock, however it ended.
+struct GameGuard<'a> {
+    state: MutexGuard<'a, GameState>,
+    version: &'a AtomicU64,
+    archive: &'a Mutex<Vec<FinishedGame>>,
+    was_finished: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct Player {
-    name: String,
-    cards: Vec<Card>,
+impl Drop for GameGuard<'_> {
+    fn drop(&mut self) {
+        if !self.was_finished && self.state.phase == Phase::Finished {
+            self.state.finished_at = now_secs();
+            self.archive
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(FinishedGame::of(&self.state));
+        }
+    }
 }
 
-impl Player {
-    fn new(name: &str) -> Self {
+/// The little kept of a game once it is over.
+struct FinishedGame {
+    winner: Option<String>,
+    turns: u32,
+    suggestions: Vec<Suggestion>,
+}
+
+impl FinishedGame {
+    fn of(state: &GameState) -> Self {
         Self {
-            name: name.to_owned(),
-            cards: Vec::<Card>::new(),
+            winner: state.winner.clone(),
+            turns: state.turn_number,
+            suggestions: state
+                .history
+                .iter()
+                .map(|record| record.suggestion.clone())
+                .collect(),
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
-enum Card {
-    Suspect(Suspect),
-    Weapon(Weapon),
-    Room(Room),
+#[derive(Serialize)]
+struct Stats<'a> {
+    games_played: usize,
+    wins: BTreeMap<&'a str, u32>,
+    average_turns: Option<f64>,
+    most_suggested: MostSuggested,
 }
 
-#[derive(Debug, Clone, EnumIter, PartialEq, Serialize, Deserialize)]
-enum Suspect {
-    Plum,
-    Green,
-    Mustard,
-    Peacock,
-    Scarlett,
-    Orchid,
+#[derive(Serialize)]
+struct MostSuggested {
+    suspect: Option<Suspect>,
+    weapon: Option<Weapon>,
+    room: Option<Room>,
 }
 
-#[derive(Debug, Clone, EnumIter, PartialEq, Serialize, Deserialize)]
-enum Weapon {
-    Candlestick,
-    LeadPipe,
-    Dagger,
-    Rope,
-    Revolver,
-    Wrench,
+impl<'a> Stats<'a> {
+    fn of(games: &'a [FinishedGame]) -> Self {
+        let mut wins = BTreeMap::new();
+        for winner in games.iter().filter_map(|game| game.winner.as_deref()) {
+            *wins.entry(winner).or_default() += 1;
+        }
+
+        let total_turns: u32 = games.iter().map(|game| game.turns).sum();
+        let suggestions: Vec<&Suggestion> = games.iter().flat_map(|g| &g.suggestions).collect();
+
+        Self {
+            games_played: games.len(),
+            wins,
+            average_turns: (!games.is_empty()).then(|| f64::from(total_turns) / games.len() as f64),
+            most_suggested: MostSuggested {
+                suspect: most_common(Suspect::iter(), |s| {
+                    suggestions.iter().filter(|x| x.suspect == *s).count()
+                }),
+                weapon: most_common(Weapon::iter(), |w| {
+                    suggestions.iter().filter(|x| x.weapon == *w).count()
+                }),
+                room: most_common(Room::iter(), |r| {
+                    suggestions.iter().filter(|x| x.room == *r).count()
+                }),
+            },
+        }
+    }
 }
 
-#[derive(Debug, Clone, EnumIter, PartialEq, Serialize, Deserialize)]
-enum Room {
-    Kitchen,
-    Hall,
-    Lounge,
-    Ballroom,
-    Conservatory,
-    DiningRoom,
-    Library,
-    BilliardRoom,
-    Study,
+/// The item counted most often, preferring the earliest on ties; `None` if
+/// nothing was counted at all.
+fn most_common<T>(items: impl Iterator<Item = T>, count: impl Fn(&T) -> usize) -> Option<T> {
+    items
+        .map(|item| (count(&item), item))
+        .filter(|(n, _)| *n > 0)
+        .reduce(|best, next| if next.0 > best.0 { next } else { best })
+        .map(|(_, item)| item)
 }
 
-// struct Won(i8);
+impl Deref for GameGuard<'_> {
+    type Target = GameState;
 
-#[post("/players/<name>")]
-fn create_player(name: &str, game_state: &State<Mutex<GameState>>) -> Status {
-    let mut state = game_state.lock().expect("Failed to lock GameState");
+    fn deref(&self) -> &GameState {
+        &self.state
+    }
+}
 
-    match state.players.iter().find(|p| p.name == name.to_owned()) {
-        Some(_) => Status::Conflict,
-        None => {
-            state.players.push(Player::new(name));
-            Status::Created
+impl DerefMut for GameGuard<'_> {
+    fn deref_mut(&mut self) -> &mut GameState {
+        self.version.fetch_add(1, Ordering::Relaxed);
+        &mut self.state
+    }
+}
+
+/// Serialized public views, reused until the game's version moves on so
+/// repeated polling skips serialization.
+#[derive(Default)]
+struct ViewCache(Mutex<HashMap<(&'static str, Encoding), CachedView>>);
+
+struct CachedView {
+    version: u64,
+    body: (ContentType, Vec<u8>),
+}
+
+impl ViewCache {
+    fn get_or_render(
+        &self,
+        view: &'static str,
+        encoding: Encoding,
+        version: u64,
+        render: impl FnOnce() -> (ContentType, Vec<u8>),
+    ) -> (ContentType, Vec<u8>) {
+        let mut views = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match views.get(&(view, encoding)) {
+            Some(cached) if cached.version == version => cached.body.clone(),
+            _ => {
+                let body = render();
+                views.insert(
+                    (view, encoding),
+                    CachedView {
+                        version,
+                        body: body.clone(),
+                    },
+                );
+                body
+            }
         }
     }
 }
 
-#[delete("/players/<name>")]
-fn delete_player(name: &str, game_state: &State<Mutex<GameState>>) -> Status {
-    let mut state = game_state.lock().expect("Failed to lock GameState");
+/// Operational events, kept apart from the game so a reset doesn't wipe them.
+/// Clones share one log, so `SharedGame` writes to the one handlers see.
+#[derive(Clone, Default)]
+struct AuditLog(Arc<Mutex<Vec<AuditEntry>>>);
 
-    match state.players.iter().position(|p| p.name == name.to_owned()) {
-        Some(index) => {
-            state.players.remove(index);
-            Status::NoContent
+#[derive(Debug, Clone, Serialize)]
+struct AuditEntry {
+    /// Seconds since the Unix epoch.
+    at: u64,
+    action: String,
+    detail: String,
+}
+
+impl AuditLog {
+    fn entries(&self) -> Vec<AuditEntry> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn record(&self, action: &str, detail: String) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(AuditEntry {
+                at: now_secs(),
+                action: action.to_owned(),
+                detail,
+            });
+    }
+}
+
+/// Seconds since the Unix epoch.
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Answers reads that ran longer than the timeout with a 503, as for
+/// requests that gave up waiting for the game, and audits both. A write
+/// that ran over has already been applied, so its answer is left alone
+/// rather than inviting a retry; it is only audited.
+struct RequestTimer {
+    timeout: Duration,
+}
+
+struct RequestStart(Instant);
+
+#[rocket::async_trait]
+impl Fairing for RequestTimer {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request timer",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| RequestStart(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let elapsed = request
+            .local_cache(|| RequestStart(Instant::now()))
+            .0
+            .elapsed();
+        let timed_out = response.status() == Status::ServiceUnavailable;
+        let ran_over = elapsed > self.timeout;
+
+        if !timed_out && !ran_over {
+            return;
+        }
+
+        let route = request
+            .route()
+            .map_or_else(|| request.uri().to_string(), |r| r.uri.to_string());
+
+        if let Some(audit) = request.rocket().state::<AuditLog>() {
+            audit.record(
+                if timed_out { "timeout" } else { "slow request" },
+                format!(
+                    "{} {route} took {}ms",
+                    request.method(),
+                    elapsed.as_millis()
+                ),
+            );
+        }
+
+        if ran_over && !timed_out && matches!(request.method(), Method::Get | Method::Head) {
+            let error = ApiError::new(Status::ServiceUnavailable, "request took too long")
+                .with_retry_after(1);
+            if let Ok(late) = error.respond_to(request) {
+                response.merge(late);
+            }
         }
-        None => Status::NotFound,
     }
 }
 
-#[get("/players")]
-fn get_players(game_state: &State<Mutex<GameState>>) -> (ContentType, String) {
-    let players = &game_state.lock().expect("Failed to lock GameState").players;
+/// Deals tried before `create_game` gives up on a dealer.
+const DEAL_ATTEMPTS: u32 = 3;
 
-    (ContentType::JSON, to_string(&players).unwrap())
+/// Puts the cards in the order they are dealt out.
+trait Dealer: Send + Sync {
+    fn order(&self, cards: &mut [Card], rng: &mut dyn RngCore);
 }
 
-#[get("/players/<name>")]
-fn get_player(
-    name: &str,
-    game_state: &State<Mutex<GameState>>,
-) -> Result<(ContentType, String), Status> {
-    let players = &game_state.lock().expect("Failed to lock members").players;
+struct RandomDealer;
 
-    if let Some(index) = players.iter().position(|p| p.name == name.to_owned()) {
-        Ok((ContentType::JSON, to_string(&players[index]).unwrap()))
-    } else {
-        Err(Status::NotFound)
+impl Dealer for RandomDealer {
+    fn order(&self, cards: &mut [Card], rng: &mut dyn RngCore) {
+        cards.shuffle(rng);
     }
 }
 
-#[post("/game")]
-fn create_game(
-    game_state: &State<Mutex<GameState>>,
-) -> Result<(Status, (ContentType, String)), Status> {
-    let mut state = game_state.lock().expect("Failed to lock solution");
+/// Deals the cards in catalogue order, so hands are predictable.
+struct SequentialDealer;
+
+impl Dealer for SequentialDealer {
+    fn order(&self, _cards: &mut [Card], _rng: &mut dyn RngCore) {}
+}
+
+/// Debug builds may set `dealer = "sequential"` to skip the shuffle.
+fn configured_dealer(figment: &Figment) -> Box<dyn Dealer> {
+    if cfg!(debug_assertions)
+        && figment.extract_inner::<String>("dealer").as_deref() == Ok("sequential")
+    {
+        return Box::new(SequentialDealer);
+    }
+
+    Box::new(RandomDealer)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GameState {
+    players: Vec<Player>,
+    solution: Option<Suggestion>,
+    host: String,
+    phase: Phase,
+    paused: bool,
+    /// When the game was last paused, in seconds since the Unix epoch.
+    paused_at: u64,
+    settings: GameSettings,
+    history: Vec<SuggestionRecord>,
+    /// Turns on which the active player said they wouldn't suggest.
+    #[serde(default)]
+    declined_suggestions: Vec<DeclinedSuggestion>,
+    /// Player names in seating order, fixed when the game starts.
+    turn_order: Vec<String>,
+    /// Seat in `turn_order` whose turn it is.
+    current_turn: usize,
+    /// Counts turns from 1 once the game starts.
+    turn_number: u32,
+    turn: TurnState,
+    /// When the current turn began, in seconds since the Unix epoch.
+    turn_started_at: u64,
+    move_history: Vec<MoveRecord>,
+    accusations: Vec<AccusationRecord>,
+    winner: Option<String>,
+    /// When the game started, in seconds since the Unix epoch.
+    created_at: u64,
+    /// Finished by running past `max_duration_secs` rather than by a win.
+    timed_out: bool,
+    #[serde(default)]
+    intrigue_played: Vec<IntriguePlay>,
+    /// When the disproof still being chosen was asked for, in seconds since
+    /// the Unix epoch.
+    #[serde(default)]
+    disproof_started_at: u64,
+    /// Bumped by every accepted turn action, and whenever the turn passes
+    /// on or a player drops out, so a client echoing a stale value in
+    /// `X-Action-Seq` can be told it lost a race.
+    #[serde(default)]
+    action_seq: u64,
+    /// The card shown to everyone at the start, under `starting_hint`.
+    #[serde(default)]
+    starting_hint: Option<Card>,
+    /// The deck's `set_aside` cards, face up on the table.
+    #[serde(default)]
+    set_aside: Vec<Card>,
+    /// What has happened this game, for clients catching up.
+    #[serde(default)]
+    events: Vec<EventRecord>,
+    /// The sequence number of the last event, kept across resets so it
+    /// never goes backwards.
+    #[serde(default)]
+    event_seq: u64,
+    /// When the game finished, in seconds since the Unix epoch.
+    #[serde(default)]
+    finished_at: u64,
+    /// Tells this game apart from the ones before it; drawn afresh each
+    /// time the table is cleared.
+    #[serde(default = "new_game_id")]
+    id: String,
+    /// When the table was cleared for this game, in seconds since the Unix
+    /// epoch.
+    #[serde(default)]
+    opened_at: u64,
+}
+
+fn new_game_id() -> String {
+    format!("{:016x}", rng().random::<u64>())
+}
+
+/// Something that happened at the table. Suggestions and reveals point
+/// into `history`, and are shown as the viewer may see that record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum GameEvent {
+    Join { player: String },
+    Start,
+    Suggest { suggestion: usize },
+    Reveal { suggestion: usize },
+    Accuse { player: String, correct: bool },
+    Turn { player: String, turn: u32 },
+    NoSuggestion { player: String, turn: u32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EventRecord {
+    seq: u64,
+    #[serde(flatten)]
+    event: GameEvent,
+}
+
+/// What the active player has done so far this turn.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TurnState {
+    /// The dice total, once rolled.
+    roll: Option<u8>,
+    moved: bool,
+    suggested: bool,
+    /// The player said they won't suggest this turn.
+    #[serde(default)]
+    declined_suggestion: bool,
+}
+
+/// An accusation is announced to everyone, right or wrong.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AccusationRecord {
+    player: String,
+    accusation: Suggestion,
+    correct: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct AccusationResult<'a> {
+    correct: bool,
+    solution: &'a Suggestion,
+}
+
+/// Where one player believes a card to be.
+enum CardKnowledge<'a> {
+    Mine,
+    HeldBy(&'a str),
+    /// Set aside face up, so nobody holds it.
+    OnTheTable,
+    /// Held by one of these players, or in the envelope.
+    Unknown(Vec<&'a str>),
+}
+
+#[derive(Debug, Serialize)]
+struct EliminatedPlayer<'a> {
+    name: &'a str,
+    reason: Option<&'a EliminationReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    accusation: Option<&'a Suggestion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EliminationReason {
+    WrongAccusation,
+    Resigned,
+}
+
+/// A keeper card played, announced to everyone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IntriguePlay {
+    player: String,
+    turn: u32,
+    card: Keeper,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MoveRecord {
+    player: String,
+    turn: u32,
+    room: Room,
+}
+
+impl GameState {
+    fn new() -> Self {
+        GameState {
+            players: Vec::new(),
+            solution: None,
+            host: String::new(),
+            phase: Phase::Lobby,
+            paused: false,
+            paused_at: 0,
+            settings: GameSettings::default(),
+            history: Vec::new(),
+            declined_suggestions: Vec::new(),
+            turn_order: Vec::new(),
+            current_turn: 0,
+            turn_number: 0,
+            turn: TurnState::default(),
+            turn_started_at: 0,
+            move_history: Vec::new(),
+            accusations: Vec::new(),
+            winner: None,
+            created_at: 0,
+            timed_out: false,
+            intrigue_played: Vec::new(),
+            disproof_started_at: 0,
+            action_seq: 0,
+            starting_hint: None,
+            set_aside: Vec::new(),
+            events: Vec::new(),
+            event_seq: 0,
+            finished_at: 0,
+            id: new_game_id(),
+            opened_at: now_secs(),
+        }
+    }
 
-    match state.solution {
-        Some(_) => (),
-        None => return Err(Status::BadRequest),
+    fn record_event(&mut self, event: GameEvent) {
+        self.event_seq += 1;
+        self.events.push(EventRecord {
+            seq: self.event_seq,
+            event,
+        });
     }
 
-    if state.players.len() < 2 {
-        return Err(Status::BadRequest);
+    /// The events after `seq`, each as `viewer` may see it.
+    fn events_since(&self, seq: u64, viewer: Option<&str>, is_admin: bool) -> Vec<Value> {
+        self.events
+            .iter()
+            .filter(|record| record.seq > seq)
+            .map(|record| {
+                let mut value = to_value(record).unwrap();
+                if let GameEvent::Suggest { suggestion } | GameEvent::Reveal { suggestion } =
+                    record.event
+                {
+                    let shown = is_admin || self.shown_publicly(suggestion);
+                    value["record"] = json!(self.history[suggestion].public(viewer, shown));
+                }
+                value
+            })
+            .collect()
     }
 
-    let mut rng = rng();
+    /// Index of the player whose turn it is.
+    fn active_player(&self) -> Option<usize> {
+        self.turn_order
+            .get(self.current_turn)
+            .and_then(|name| self.player_index(name))
+    }
 
-    let suspects: Vec<Suspect> = Suspect::iter().collect();
-    let weapons: Vec<Weapon> = Weapon::iter().collect();
-    let rooms: Vec<Room> = Room::iter().collect();
+    /// Hands the turn to the next seat with a player still in the game.
+    fn advance_turn(&mut self) {
+        let seats = self.turn_order.len();
+        for _ in 0..seats {
+            self.current_turn = (self.current_turn + 1) % seats;
+            if self
+                .active_player()
+                .is_some_and(|index| !self.players[index].eliminated)
+            {
+                break;
+            }
+        }
 
-    let murder_suspect = suspects.choose(&mut rng).unwrap();
-    let murder_weapon = weapons.choose(&mut rng).unwrap();
-    let murder_room = rooms.choose(&mut rng).unwrap();
+        self.turn_number += 1;
+        self.turn = TurnState::default();
+        self.turn_started_at = now_secs();
+        self.action_seq += 1;
 
-    state.solution = Some(Suggestion {
-        suspect: murder_suspect.clone(),
-        weapon: murder_weapon.clone(),
-        room: murder_room.clone(),
-    });
+        if let Some(index) = self.active_player() {
+            let player = self.players[index].name.clone();
+            let turn = self.turn_number;
+            self.record_event(GameEvent::Turn { player, turn });
+        }
+    }
+
+    /// Takes the player at `index` out of the game. The last player left in
+    /// wins; otherwise play moves on if it was their turn.
+    fn eliminate(&mut self, index: usize, reason: EliminationReason) {
+        self.players[index].eliminated = true;
+        self.players[index].eliminated_reason = Some(reason);
+        self.action_seq += 1;
+
+        let mut remaining = self.players.iter().filter(|p| !p.eliminated);
+        if let (Some(last), None) = (remaining.next(), remaining.next()) {
+            self.winner = Some(last.name.clone());
+            self.phase = Phase::Finished;
+        } else if self.active_player() == Some(index) {
+            self.advance_turn();
+        }
+    }
 
-    let mut all_cards = Vec::new();
+    /// Clears the table back to an empty lobby with default settings.
+    fn reset(&mut self) {
+        self.players = Vec::new();
+        self.solution = None;
+        self.host = String::new();
+        self.phase = Phase::Lobby;
+        self.paused = false;
+        self.settings = GameSettings::default();
+        self.history = Vec::new();
+        self.declined_suggestions = Vec::new();
+        self.turn_order = Vec::new();
+        self.current_turn = 0;
+        self.turn_number = 0;
+        self.turn = TurnState::default();
+        self.move_history = Vec::new();
+        self.accusations = Vec::new();
+        self.winner = None;
+        self.timed_out = false;
+        self.intrigue_played = Vec::new();
+        self.starting_hint = None;
+        self.set_aside = Vec::new();
+        self.events = Vec::new();
+        self.id = new_game_id();
+        self.opened_at = now_secs();
+    }
 
-    all_cards.extend(Suspect::iter().map(Card::Suspect));
-    all_cards.extend(Weapon::iter().map(Card::Weapon));
-    all_cards.extend(Room::iter().map(Card::Room));
+    /// Applies whatever has come due by `now`: a turn that ran past the turn
+    /// timer is forfeited.
+    fn tick(&mut self, now: u64, audit: &AuditLog) -> bool {
+        if self.phase == Phase::Finished {
+            if self.retention_left(now) == Some(0) {
+                audit.record(
+                    "finished game cleared",
+                    format!("won by {}", self.winner.as_deref().unwrap_or("nobody")),
+                );
+                self.reset();
+                return true;
+            }
+            return false;
+        }
+        if self.phase != Phase::InProgress {
+            return false;
+        }
 
-    let solution_vector: Vec<Card> = vec![
-        Card::Suspect(murder_suspect.to_owned()),
-        Card::Weapon(murder_weapon.to_owned()),
-        Card::Room(murder_room.to_owned()),
-    ];
-    all_cards.retain(|x| !solution_vector.contains(x));
+        // Runs on wall-clock time, pauses included, so abandoned games end.
+        if let Some(limit) = self.settings.max_duration_secs
+            && now.saturating_sub(self.created_at) >= limit
+        {
+            self.phase = Phase::Finished;
+            self.timed_out = true;
+            return true;
+        }
 
-    all_cards.shuffle(&mut rng);
+        if self.paused {
+            return false;
+        }
 
-    let total_cards = all_cards.len();
-    let num_players = state.players.len();
+        if let Some(limit) = self.settings.disproof_timeout_secs
+            && self.pending_disproof().is_some()
+            && now.saturating_sub(self.disproof_started_at) >= limit
+        {
+            if let Some(settled) = self.settle_pending_disproof() {
+                audit.record("disproof timed out", settled);
+            }
+            return true;
+        }
 
-    let base_cards_per_player = total_cards / num_players;
-    let extra_cards = total_cards % num_players;
+        if let Some(limit) = self.settings.turn_timer_secs
+            && now.saturating_sub(self.turn_started_at) >= limit
+        {
+            self.advance_turn();
+            return true;
+        }
 
-    let mut card_index = 0;
+        false
+    }
 
-    for _ in 0..base_cards_per_player {
-        for player_index in 0..num_players {
-            if card_index < all_cards.len() {
-                state.players[player_index]
-                    .cards
-                    .push(all_cards[card_index].clone());
-                card_index += 1;
+    /// Seconds until a finished game under `finished_retention_secs` is
+    /// cleared away.
+    fn retention_left(&self, now: u64) -> Option<u64> {
+        let retention = self.settings.finished_retention_secs?;
+        (self.phase == Phase::Finished).then(|| (self.finished_at + retention).saturating_sub(now))
+    }
+
+    /// The turn actions the player at `index` may take right now. Only the
+    /// active player of a running, unpaused game has any.
+    fn available_actions(&self, index: usize) -> Vec<&'static str> {
+        if self.phase != Phase::InProgress || self.paused || self.active_player() != Some(index) {
+            return Vec::new();
+        }
+
+        let mut actions = Vec::new();
+        if !self.turn.moved {
+            if self.turn.roll.is_none() {
+                actions.push("roll");
             }
+            actions.push("move");
         }
+        let in_room = matches!(self.players[index].location, Some(Position::Room(_)));
+        if !self.turn.suggested
+            && !self.turn.declined_suggestion
+            && (in_room || self.suggests_anywhere(index))
+            && (self.turn.moved || !self.settings.require_move_before_suggest)
+        {
+            actions.extend(["suggest", "no-suggestion"]);
+        }
+        if self.pending_disproof().is_none() {
+            actions.push("accuse");
+            if self.ensure_suggestion_decided(index).is_ok() {
+                actions.push("end-turn");
+            }
+        }
+
+        actions
     }
 
-    for player_index in 0..extra_cards {
-        if card_index < all_cards.len() {
-            state.players[player_index]
-                .cards
-                .push(all_cards[card_index].clone());
-            card_index += 1;
+    /// Whether the player at `index` has played Suggest Anywhere this turn,
+    /// freeing their suggestion from the room they stand in.
+    fn suggests_anywhere(&self, index: usize) -> bool {
+        self.intrigue_played.iter().any(|play| {
+            play.turn == self.turn_number
+                && play.player == self.players[index].name
+                && play.card == Keeper::SuggestAnywhere
+        })
+    }
+
+    /// The active player's roll for this turn.
+    fn roll(&mut self) -> Result<u8, ApiError> {
+        if self.turn.moved {
+            return Err(ApiError::new(Status::Conflict, "already moved this turn"));
         }
+        if self.turn.roll.is_some() {
+            return Err(ApiError::new(Status::Conflict, "already rolled this turn"));
+        }
+
+        let roll = self.roll_dice();
+        self.turn.roll = Some(roll);
+
+        Ok(roll)
     }
 
-    Ok((
-        Status::Created,
-        (ContentType::JSON, to_string(&*state).unwrap()),
-    ))
-}
+    /// Moves the active player, at `mover`, into a room.
+    fn move_token(&mut self, mover: usize, request: MoveRequest) -> Result<(), ApiError> {
+        if self.turn.moved {
+            return Err(ApiError::new(Status::Conflict, "already moved this turn"));
+        }
+        self.ensure_in_deck(&[Card::Room(request.room.clone())])?;
+
+        if self.players[mover].location == Some(Position::Room(request.room.clone())) {
+            return Err(ApiError::new(Status::BadRequest, "already in that room"));
+        }
+
+        if request.secret_passage {
+            let connected = match &self.players[mover].location {
+                Some(Position::Room(from)) => {
+                    board::connects(&self.settings.secret_passages, from, &request.room)
+                }
+                _ => false,
+            };
+            if !connected {
+                return Err(ApiError::new(
+                    Status::Conflict,
+                    "no secret passage from here to that room",
+                ));
+            }
+        }
+
+        let record = MoveRecord {
+            player: self.players[mover].name.clone(),
+            turn: self.turn_number,
+            room: request.room,
+        };
 
-#[delete("/game")]
-fn delete_game(game_state: &State<Mutex<GameState>>) -> Status {
-    let mut state = game_state.lock().expect("Failed to lock solution");
+        self.players[mover].location = Some(Position::Room(record.room.clone()));
+        self.turn.moved = true;
+        self.move_history.push(record);
 
-    match state.solution {
-        Some(_) => (),
-        None => return Status::BadRequest,
+        Ok(())
     }
 
-    state.players = Vec::new();
-    state.solution = None;
+    /// Makes the active player's suggestion and asks round the table for a
+    /// disproof, recording the result at the end of `history`. Says whether
+    /// the same trio had been suggested before.
+    fn suggest(&mut self, suggester: usize, suggestion: Suggestion) -> Result<bool, ApiError> {
+        if self.turn.suggested {
+            return Err(ApiError::new(
+                Status::Conflict,
+                "already suggested this turn",
+            ));
+        }
+        if self.turn.declined_suggestion {
+            return Err(ApiError::new(
+                Status::Conflict,
+                "declared no suggestion this turn",
+            ));
+        }
+        self.ensure_in_deck(&suggestion.as_cards())?;
 
-    Status::NoContent
-}
+        if self.settings.forbid_self_suspect
+            && self.players[suggester].character.as_ref() == Some(&suggestion.suspect)
+        {
+            return Err(ApiError::new(
+                Status::UnprocessableEntity,
+                "cannot suggest your own character",
+            )
+            .with_details(json!({ "character": suggestion.suspect })));
+        }
 
-#[post("/suggest", data = "<suggestion>")]
-fn suggest(suggestion: Json<Suggestion>, game_state: &State<Mutex<GameState>>) -> Status {
-    let state = &game_state.lock().expect("Failed to lock GameState");
-
-    for player in &state.players {
-        player.cards.iter().find(|c| {
-            *c == &Card::Suspect(suggestion.0.suspect.clone())
-                || *c == &Card::Weapon(suggestion.0.weapon.clone())
-                || *c == &Card::Room(suggestion.0.room.clone())
+        let location = self.players[suggester].location.as_ref();
+        if location != Some(&Position::Room(suggestion.room.clone()))
+            && !self.suggests_anywhere(suggester)
+        {
+            return Err(
+                ApiError::new(Status::Conflict, "not in the suggested room").with_details(json!({
+                    "room": suggestion.room,
+                    "location": location,
+                })),
+            );
+        }
+
+        if self.settings.require_move_before_suggest && !self.turn.moved {
+            return Err(
+                ApiError::new(Status::Conflict, "must move into the suggested room first")
+                    .with_details(json!({ "room": suggestion.room })),
+            );
+        }
+
+        let cards = suggestion.as_cards();
+
+        let mut passed = Vec::new();
+        let mut outcome = DisproofOutcome::AllPassed;
+        for index in self.asking_order(&self.players[suggester].name) {
+            let player = &self.players[index];
+            let Some(card) = player.cards.iter().find(|c| cards.contains(c)) else {
+                passed.push(player.name.clone());
+                continue;
+            };
+
+            let by = player.name.clone();
+            outcome = match self.settings.disproof_mode {
+                DisproofMode::Automatic => DisproofOutcome::Disproved {
+                    by,
+                    shown_to: self.players[suggester].name.clone(),
+                    card: card.clone(),
+                },
+                DisproofMode::Choose => DisproofOutcome::Pending { by },
+            };
+            break;
+        }
+
+        let duplicate = self
+            .history
+            .iter()
+            .any(|record| record.suggestion.as_cards() == cards);
+
+        // The suggested suspect is called into the room, whoever plays them.
+        let room = Position::Room(suggestion.room.clone());
+        if let Some(suspect) = self
+            .players
+            .iter_mut()
+            .find(|player| player.character.as_ref() == Some(&suggestion.suspect))
+        {
+            suspect.location = Some(room);
+        }
+
+        let record = SuggestionRecord {
+            suggested_by: self.players[suggester].name.clone(),
+            suggestion,
+            passed,
+            outcome,
+        };
+
+        if matches!(record.outcome, DisproofOutcome::Pending { .. }) {
+            self.disproof_started_at = now_secs();
+        }
+        self.history.push(record);
+        self.record_event(GameEvent::Suggest {
+            suggestion: self.history.len() - 1,
+        });
+        self.turn.suggested = true;
+
+        Ok(duplicate)
+    }
+
+    /// What the suggester is told about the suggestion just made.
+    fn last_suggestion_result(&self, duplicate: bool) -> SuggestionResult<'_> {
+        let record = self.history.last().unwrap();
+
+        let unheld = self.settings.suggestion_analytics.then(|| {
+            record
+                .suggestion
+                .as_cards()
+                .into_iter()
+                .filter(|card| !self.players.iter().any(|p| p.cards.contains(card)))
+                .collect()
         });
+
+        SuggestionResult {
+            suggested_by: &record.suggested_by,
+            outcome: &record.outcome,
+            duplicate,
+            unheld,
+        }
+    }
+
+    /// Rolls two dice for this turn. Seeded by `dice_seed` and the turn
+    /// number, so a replayed game rolls the same.
+    fn roll_dice(&self) -> u8 {
+        let seed = self.settings.dice_seed.unwrap_or_default() ^ u64::from(self.turn_number);
+        let mut dice = StdRng::seed_from_u64(seed);
+
+        dice.random_range(1..=6) + dice.random_range(1..=6)
+    }
+
+    /// Steps the player at `index` has left this turn. Every move ends in a
+    /// room, which uses up the rest of the roll.
+    fn remaining_steps(&self, index: usize) -> u8 {
+        if self.phase != Phase::InProgress || self.active_player() != Some(index) || self.turn.moved
+        {
+            return 0;
+        }
+
+        self.turn.roll.unwrap_or(0)
     }
 
-    todo!()
+    /// Index into `history` of the suggestion still waiting on a reveal.
+    fn pending_disproof(&self) -> Option<usize> {
+        self.history
+            .iter()
+            .rposition(|record| matches!(record.outcome, DisproofOutcome::Pending { .. }))
+    }
+
+    /// Shows a card on behalf of a disprover who is still choosing, picked
+    /// by `disproof_timeout_pick`. Describes what it did, for the audit.
+    fn settle_pending_disproof(&mut self) -> Option<String> {
+        let pending = self.pending_disproof()?;
+
+        let record = &self.history[pending];
+        let by = record.outcome.disprover().unwrap_or_default().to_owned();
+        let suggested = record.suggestion.as_cards();
+        let matching: Vec<&Card> = self
+            .player_index(&by)
+            .map(|index| {
+                self.players[index]
+                    .cards
+                    .iter()
+                    .filter(|card| suggested.contains(card))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let card = match self.settings.disproof_timeout_pick {
+            DisproofPick::Random => matching.choose(&mut rng()).copied(),
+            DisproofPick::Lowest => {
+                let order = self.settings.deck.cards();
+                matching
+                    .into_iter()
+                    .min_by_key(|card| order.iter().position(|c| c == *card))
+            }
+        }
+        .cloned();
+
+        let shown_to = record.suggested_by.clone();
+        let settled = format!(
+            "suggestion {pending} by {shown_to}: {by} showed {}",
+            card.as_ref().map_or("nothing".to_owned(), Card::name)
+        );
+        self.history[pending].outcome = match card {
+            Some(card) => DisproofOutcome::Disproved { by, shown_to, card },
+            None => DisproofOutcome::AllPassed,
+        };
+        self.record_event(GameEvent::Reveal {
+            suggestion: pending,
+        });
+
+        Some(settled)
+    }
+
+    /// Under `require_move_before_suggest`, a player who has moved into a
+    /// room must suggest or say they won't before the turn can end.
+    fn ensure_suggestion_decided(&self, index: usize) -> Result<(), ApiError> {
+        let in_room = matches!(self.players[index].location, Some(Position::Room(_)));
+        if self.settings.require_move_before_suggest
+            && self.turn.moved
+            && in_room
+            && !self.turn.suggested
+            && !self.turn.declined_suggestion
+        {
+            return Err(ApiError::new(
+                Status::Conflict,
+                "suggest or declare no suggestion first",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The active player can't move on while a disprover is still choosing.
+    fn ensure_no_pending_disproof(&self) -> Result<(), ApiError> {
+        match self.pending_disproof() {
+            Some(_) => Err(ApiError::new(Status::Conflict, "waiting for a disproof")),
+            None => Ok(()),
+        }
+    }
+
+    /// Rooms and cards a player names must be in the deck being played.
+    fn ensure_in_deck(&self, cards: &[Card]) -> Result<(), ApiError> {
+        let deck = self.settings.deck.cards();
+        match cards.iter().find(|card| !deck.contains(card)) {
+            Some(card) => Err(
+                ApiError::new(Status::UnprocessableEntity, "card not in play")
+                    .with_details(json!({ "card": card })),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Player actions are refused while the host has the game paused.
+    fn ensure_not_paused(&self) -> Result<(), ApiError> {
+        if self.paused {
+            Err(ApiError::new(Status::Conflict, "game paused")
+                .with_details(json!({ "paused_at": self.paused_at }))
+                .with_retry_after(PAUSED_RETRY_SECS))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A finished game under `finished_retention_secs` stays as it ended
+    /// until it is cleared away.
+    fn ensure_not_retained(&self) -> Result<(), ApiError> {
+        match self.retention_left(now_secs()) {
+            Some(left) => Err(
+                ApiError::new(Status::Conflict, "finished game is kept for review")
+                    .with_details(json!({ "seconds_left": left }))
+                    .with_retry_after(left),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    fn player_index(&self, name: &str) -> Option<usize> {
+        let key = name_key(name);
+        self.players.iter().position(|p| name_key(&p.name) == key)
+    }
+
+    /// Starts the game with `settings`, seating players in join order.
+    fn start(
+        &mut self,
+        mut settings: GameSettings,
+        dealer: &dyn Dealer,
+        audit: &AuditLog,
+    ) -> Result<(), ApiError> {
+        let problems = self.start_problems(&settings);
+        if !problems.is_empty() {
+            return Err(ApiError::new(Status::Conflict, "game cannot start")
+                .with_details(json!({ "phase": self.phase, "problems": problems })));
+        }
+
+        settings.pick_seeds();
+
+        self.settings = settings;
+        self.turn_order = self.players.iter().map(|p| p.name.clone()).collect();
+        if self.settings.randomize_turn_order {
+            let seating_seed = self.settings.seating_seed.unwrap();
+            self.turn_order
+                .shuffle(&mut StdRng::seed_from_u64(seating_seed));
+        }
+        self.current_turn = 0;
+        self.turn_number = 1;
+        self.created_at = now_secs();
+        self.turn_started_at = self.created_at;
+
+        self.deal(dealer, audit)?;
+        self.starting_hint = None;
+        if self.settings.starting_hint {
+            self.give_starting_hint();
+        }
+
+        for player in &mut self.players {
+            player.location = player
+                .character
+                .as_ref()
+                .map(|character| Position::Square(Square::start_of(character)));
+        }
+
+        self.phase = Phase::InProgress;
+        self.record_event(GameEvent::Start);
+
+        Ok(())
+    }
+
+    /// Shows everyone one dealt card, drawn from the deal seed, and marks it
+    /// in every notebook. Dealt cards are never in the envelope.
+    fn give_starting_hint(&mut self) {
+        let dealt: Vec<(String, Card)> = self
+            .players
+            .iter()
+            .flat_map(|player| {
+                player
+                    .cards
+                    .iter()
+                    .map(|card| (player.name.clone(), card.clone()))
+            })
+            .collect();
+        let deal_seed = self.settings.deal_seed.unwrap();
+        let Some((holder, card)) = dealt.choose(&mut StdRng::seed_from_u64(deal_seed)).cloned()
+        else {
+            return;
+        };
+
+        for player in &mut self.players {
+            let mark = if player.name == holder {
+                Mark::Have
+            } else {
+                Mark::HeldBy(holder.clone())
+            };
+            player.notebook.retain(|entry| entry.card != card);
+            player.notebook.push(NotebookMark {
+                card: card.clone(),
+                mark,
+            });
+        }
+
+        self.starting_hint = Some(card);
+    }
+
+    /// Deals a finished game again for the same players and characters,
+    /// with a fresh solution and the first seat moved on by one. Under
+    /// `randomize_turn_order` the seating is shuffled afresh instead.
+    ///
+    /// The new game is set up on a copy, so a rematch that can't start
+    /// leaves the finished game as it was.
+    fn rematch(&mut self, dealer: &dyn Dealer, audit: &AuditLog) -> Result<(), ApiError> {
+        if self.phase != Phase::Finished {
+            return Err(ApiError::new(Status::Conflict, "game not finished")
+                .with_details(json!({ "phase": self.phase })));
+        }
+
+        let mut rematch = self.clone();
+        rematch.deal_again(dealer, audit)?;
+        *self = rematch;
+
+        Ok(())
+    }
+
+    fn deal_again(&mut self, dealer: &dyn Dealer, audit: &AuditLog) -> Result<(), ApiError> {
+        let previous = std::mem::take(&mut self.turn_order);
+
+        for player in &mut self.players {
+            player.cards = Vec::new();
+            player.intrigue_cards = Vec::new();
+            player.notebook = Vec::new();
+            player.location = None;
+            player.eliminated = false;
+            player.eliminated_reason = None;
+        }
+
+        self.solution = None;
+        self.phase = Phase::Lobby;
+        self.paused = false;
+        self.history = Vec::new();
+        self.declined_suggestions = Vec::new();
+        self.turn = TurnState::default();
+        self.move_history = Vec::new();
+        self.accusations = Vec::new();
+        self.winner = None;
+        self.timed_out = false;
+        self.intrigue_played = Vec::new();
+        self.events = Vec::new();
+
+        // The old seeds would deal the very same game.
+        let mut settings = self.settings.clone();
+        settings.solution_seed = None;
+        settings.deal_seed = None;
+        settings.dice_seed = None;
+        settings.seating_seed = None;
+        settings.fixed_hands = None;
+
+        self.start(settings, dealer, audit)?;
+
+        // Players who left after the game lose their seat; the rest keep
+        // theirs, one along.
+        if !self.settings.randomize_turn_order {
+            let mut turn_order: Vec<String> = previous
+                .into_iter()
+                .filter(|name| self.players.iter().any(|player| &player.name == name))
+                .collect();
+            if turn_order.len() == self.turn_order.len() {
+                turn_order.rotate_left(1);
+                self.turn_order = turn_order;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draws the solution and deals the rest, from the seeds set by
+    /// `GameSettings::pick_seeds`, or lays out `fixed_hands` if given.
+    fn deal(&mut self, dealer: &dyn Dealer, audit: &AuditLog) -> Result<(), ApiError> {
+        match self.settings.fixed_solution(&self.players) {
+            Ok(Some(solution)) => self.deal_fixed(solution),
+            Ok(None) => self.deal_shuffled(dealer, audit)?,
+            Err(problems) => return Err(GameSettings::hands_error(problems)),
+        }
+
+        // Keepers go round on their own, after the clue cards.
+        let deal_seed = self.settings.deal_seed.unwrap();
+        let mut intrigue = self.settings.deck.intrigue.clone();
+        intrigue.shuffle(&mut StdRng::seed_from_u64(deal_seed));
+        let num_players = self.players.len();
+        for player in &mut self.players {
+            player.intrigue_cards.clear();
+        }
+        for (index, card) in intrigue.into_iter().enumerate() {
+            self.players[index % num_players].intrigue_cards.push(card);
+        }
+
+        Ok(())
+    }
+
+    /// Hands out `fixed_hands` as given, with `solution` the cards left over.
+    /// Fixed hands take the whole deck, so nothing is set aside.
+    fn deal_fixed(&mut self, solution: Suggestion) {
+        let hands = self.settings.fixed_hands.as_ref().unwrap();
+        for player in &mut self.players {
+            player.cards = hands[&player.name].clone();
+        }
+        self.solution = Some(solution);
+        self.set_aside = Vec::new();
+    }
+
+    fn deal_shuffled(&mut self, dealer: &dyn Dealer, audit: &AuditLog) -> Result<(), ApiError> {
+        let solution_seed = self.settings.solution_seed.unwrap();
+        let solution = self
+            .settings
+            .deck
+            .draw_solution(&mut StdRng::seed_from_u64(solution_seed));
+        let solution_cards = solution.as_cards();
+        self.solution = Some(solution);
+
+        // A broken dealer or odd custom deck shouldn't sink the game outright,
+        // so retry on the next seeds and keep whichever one worked.
+        let mut deal_seed = self.settings.deal_seed.unwrap();
+        let mut attempt = 1;
+        loop {
+            let mut all_cards = self.settings.deck.cards();
+            all_cards.retain(|x| !solution_cards.contains(x));
+
+            let mut shuffle = StdRng::seed_from_u64(deal_seed);
+            dealer.order(&mut all_cards, &mut shuffle);
+            if self.settings.cut_deck && all_cards.len() > 1 {
+                let cut = shuffle.random_range(1..all_cards.len());
+                all_cards.rotate_left(cut);
+            }
+            let set_aside = usize::from(self.settings.deck.set_aside).min(all_cards.len());
+            self.set_aside = all_cards.split_off(all_cards.len() - set_aside);
+
+            let num_players = self.players.len();
+            for player in &mut self.players {
+                player.cards.clear();
+            }
+            for (index, card) in all_cards.into_iter().enumerate() {
+                self.players[index % num_players].cards.push(card);
+            }
+
+            let problems = self.validate_invariants();
+            if problems.is_empty() {
+                break;
+            }
+
+            audit.record(
+                "invalid deal",
+                format!(
+                    "attempt {attempt} with seed {deal_seed}: {}",
+                    problems.join("; ")
+                ),
+            );
+
+            if attempt == DEAL_ATTEMPTS {
+                for player in &mut self.players {
+                    player.cards.clear();
+                }
+                self.solution = None;
+                self.set_aside = Vec::new();
+
+                return Err(ApiError::new(
+                    Status::InternalServerError,
+                    "could not deal a valid game",
+                )
+                .with_details(json!({ "attempts": attempt, "problems": problems })));
+            }
+
+            attempt += 1;
+            deal_seed = deal_seed.wrapping_add(1);
+        }
+        self.settings.deal_seed = Some(deal_seed);
+
+        Ok(())
+    }
+
+    /// Everything wrong with a fresh deal: each deck card must be in the
+    /// solution, set aside or in exactly one hand, and hands may differ by
+    /// one card.
+    fn validate_invariants(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let Some(solution) = &self.solution else {
+            return vec!["no solution drawn".to_owned()];
+        };
+
+        let solution_cards = solution.as_cards();
+        let dealt: Vec<&Card> = solution_cards
+            .iter()
+            .chain(&self.set_aside)
+            .chain(self.players.iter().flat_map(|p| &p.cards))
+            .collect();
+
+        let deck = self.settings.deck.cards();
+        for card in &deck {
+            let copies = dealt.iter().filter(|&&dealt| dealt == card).count();
+            if copies != 1 {
+                problems.push(format!("{card:?} dealt {copies} times"));
+            }
+        }
+        for card in dealt.iter().filter(|card| !deck.contains(card)) {
+            problems.push(format!("{card:?} is not in the deck"));
+        }
+
+        let fairness = DealFairness::of(&self.players);
+        if fairness.max_hand_size - fairness.min_hand_size > 1 {
+            problems.push(format!(
+                "hands range from {} to {} cards",
+                fairness.min_hand_size, fairness.max_hand_size
+            ));
+        }
+
+        problems
+    }
+
+    /// Everything that stops the lobby from starting a game, so the host can
+    /// fix them all at once.
+    fn start_problems(&self, settings: &GameSettings) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.phase != Phase::Lobby {
+            problems.push(format!("game is {:?}, not in the lobby", self.phase));
+        }
+
+        let num_players = self.players.len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&num_players) {
+            problems.push(format!(
+                "{num_players} players joined, need {MIN_PLAYERS} to {MAX_PLAYERS}"
+            ));
+        }
+
+        for player in &self.players {
+            match &player.character {
+                None => problems.push(format!("{} has no character", player.name)),
+                Some(character) if !settings.deck.suspects.contains(character) => {
+                    problems.push(format!(
+                        "{} plays {character:?}, who is not in the deck",
+                        player.name
+                    ))
+                }
+                Some(_) => (),
+            }
+        }
+
+        for suspect in Suspect::iter() {
+            let count = self
+                .players
+                .iter()
+                .filter(|p| p.character.as_ref() == Some(&suspect))
+                .count();
+            if count > 1 {
+                problems.push(format!("{suspect:?} is played by {count} players"));
+            }
+        }
+
+        problems
+    }
+
+    /// Whether the card shown for `history[index]` is shown to everyone:
+    /// always with `open_info`, or just the game's first disproof with
+    /// `open_first_disproof`.
+    fn shown_publicly(&self, index: usize) -> bool {
+        self.settings.open_info
+            || (self.settings.open_first_disproof
+                && self
+                    .history
+                    .iter()
+                    .position(|record| matches!(record.outcome, DisproofOutcome::Disproved { .. }))
+                    == Some(index))
+    }
+
+    /// The cards the player at `viewer` has seen: their hand, the starting
+    /// hint, and anything shown to them, or shown to everyone.
+    fn seen_cards(&self, viewer: usize) -> Vec<&Card> {
+        let me = &self.players[viewer];
+        let mut seen: Vec<&Card> = me.cards.iter().chain(&self.set_aside).collect();
+
+        if let Some(hint) = &self.starting_hint
+            && !seen.contains(&hint)
+        {
+            seen.push(hint);
+        }
+
+        for (index, record) in self.history.iter().enumerate() {
+            if let DisproofOutcome::Disproved { shown_to, card, .. } = &record.outcome
+                && (self.shown_publicly(index) || *shown_to == me.name)
+                && !seen.contains(&card)
+            {
+                seen.push(card);
+            }
+        }
+
+        seen
+    }
+
+    /// The turn order rotated to start at `name`, wrapping around the table.
+    /// `name` is matched as it is everywhere, ignoring case and accents.
+    fn turn_order_from(&self, name: &str) -> Option<Vec<&str>> {
+        let name = &self.players[self.player_index(name)?].name;
+        let seat = self.turn_order.iter().position(|seated| seated == name)?;
+        let (before, after) = self.turn_order.split_at(seat);
+
+        Some(after.iter().chain(before).map(String::as_str).collect())
+    }
+
+    /// Indices of the players asked to disprove a suggestion, in the order
+    /// they are asked.
+    fn asking_order(&self, suggester: &str) -> Vec<usize> {
+        let seats = self.turn_order.len();
+        let Some(seat) = self.turn_order.iter().position(|name| name == suggester) else {
+            return Vec::new();
+        };
+
+        (1..seats)
+            .map(|offset| match self.settings.disproof_direction {
+                DisproofDirection::Clockwise => (seat + offset) % seats,
+                DisproofDirection::Counterclockwise => (seat + seats - offset) % seats,
+            })
+            .filter_map(|seat| self.player_index(&self.turn_order[seat]))
+            .collect()
+    }
+
+    /// What the player at `viewer` knows about where `card` is, from their
+    /// hand and notebook, the cards shown to them, and who has passed on
+    /// suggestions naming it.
+    fn knowledge(&self, viewer: usize, card: &Card) -> CardKnowledge<'_> {
+        let me = &self.players[viewer];
+
+        let marked = me.notebook.iter().find(|entry| &entry.card == card);
+        if me.cards.contains(card)
+            || matches!(
+                marked,
+                Some(NotebookMark {
+                    mark: Mark::Have,
+                    ..
+                })
+            )
+        {
+            return CardKnowledge::Mine;
+        }
+        if self.set_aside.contains(card) {
+            return CardKnowledge::OnTheTable;
+        }
+        if let Some(NotebookMark {
+            mark: Mark::HeldBy(holder),
+            ..
+        }) = marked
+        {
+            return CardKnowledge::HeldBy(holder);
+        }
+        if let Some(holder) =
+            self.history
+                .iter()
+                .enumerate()
+                .find_map(|(index, record)| match &record.outcome {
+                    DisproofOutcome::Disproved {
+                        by,
+                        shown_to,
+                        card: shown,
+                    } if (*shown_to == me.name || self.shown_publicly(index)) && shown == card => {
+                        Some(by.as_str())
+                    }
+                    _ => None,
+                })
+        {
+            return CardKnowledge::HeldBy(holder);
+        }
+
+        let passed: Vec<&String> = self
+            .history
+            .iter()
+            .filter(|record| record.suggestion.as_cards().contains(card))
+            .flat_map(|record| &record.passed)
+            .collect();
+
+        CardKnowledge::Unknown(
+            self.players
+                .iter()
+                .enumerate()
+                .filter(|(index, player)| *index != viewer && !passed.contains(&&player.name))
+                .map(|(_, player)| player.name.as_str())
+                .collect(),
+        )
+    }
+
+    /// The opponents who might hold `card`, as far as `viewer` can tell.
+    fn possible_holders(&self, viewer: usize, card: &Card) -> Vec<&str> {
+        match self.knowledge(viewer, card) {
+            CardKnowledge::Mine | CardKnowledge::OnTheTable => Vec::new(),
+            CardKnowledge::HeldBy(holder) => vec![holder],
+            CardKnowledge::Unknown(candidates) => candidates,
+        }
+    }
+
+    /// The card of `category` that `viewer` can prove is in the envelope:
+    /// either every other card of it is placed in a hand, or no opponent
+    /// can be holding it.
+    fn deduced(&self, viewer: usize, category: &str) -> Option<Card> {
+        let unplaced: Vec<(Card, bool)> = self
+            .settings
+            .deck
+            .cards()
+            .into_iter()
+            .filter(|card| card.category() == category)
+            .filter_map(|card| match self.knowledge(viewer, &card) {
+                CardKnowledge::Unknown(candidates) => Some((card, candidates.is_empty())),
+                _ => None,
+            })
+            .collect();
+
+        match unplaced.as_slice() {
+            [(card, _)] => Some(card.clone()),
+            _ => unplaced
+                .into_iter()
+                .find(|(_, unheld)| *unheld)
+                .map(|(card, _)| card),
+        }
+    }
+
+    /// Where a suspect's token stands: with its player if someone plays it,
+    /// otherwise in the room it was last suggested in, or on its start.
+    fn suspect_location(&self, suspect: &Suspect) -> Option<Position> {
+        if let Some(player) = self
+            .players
+            .iter()
+            .find(|player| player.character.as_ref() == Some(suspect))
+        {
+            return player.location.clone();
+        }
+
+        let suggested_in = self
+            .history
+            .iter()
+            .rev()
+            .find(|record| &record.suggestion.suspect == suspect)
+            .map(|record| Position::Room(record.suggestion.room.clone()));
+
+        Some(suggested_in.unwrap_or(Position::Square(Square::start_of(suspect))))
+    }
+
+    /// How many cards of `category` could still be in the envelope, as far as
+    /// `viewer` can tell: one once it is pinned, otherwise every card not
+    /// yet placed in a hand.
+    fn candidate_count(&self, viewer: usize, category: &str) -> usize {
+        if self.deduced(viewer, category).is_some() {
+            return 1;
+        }
+
+        self.settings
+            .deck
+            .cards()
+            .iter()
+            .filter(|card| card.category() == category)
+            .filter(|card| matches!(self.knowledge(viewer, card), CardKnowledge::Unknown(_)))
+            .count()
+    }
+
+    /// The chance, as `viewer` sees it, that the player at `holder` has
+    /// `card`. An unplaced card is taken to be equally likely in any hand
+    /// slot `viewer` can't already account for, or in the envelope.
+    fn holding_probability(&self, viewer: usize, holder: usize, card: &Card) -> f64 {
+        let name = self.players[holder].name.as_str();
+
+        let candidates = match self.knowledge(viewer, card) {
+            CardKnowledge::Mine => return if holder == viewer { 1.0 } else { 0.0 },
+            CardKnowledge::HeldBy(known) => return if known == name { 1.0 } else { 0.0 },
+            CardKnowledge::OnTheTable => return 0.0,
+            CardKnowledge::Unknown(candidates) => candidates,
+        };
+        if !candidates.contains(&name) {
+            return 0.0;
+        }
+
+        // Hand cards `viewer` has already placed leave fewer slots open.
+        let open_slots = |player: &str| {
+            let index = self.player_index(player).unwrap();
+            let placed = self
+                .settings
+                .deck
+                .cards()
+                .iter()
+                .filter(|other| {
+                    matches!(self.knowledge(viewer, other), CardKnowledge::HeldBy(known) if known == player)
+                })
+                .count();
+
+            self.players[index].cards.len().saturating_sub(placed)
+        };
+
+        let envelope = if matches!(card, Card::Clock(_)) { 0 } else { 1 };
+        let total: usize = candidates.iter().map(|&c| open_slots(c)).sum::<usize>() + envelope;
+        if total == 0 {
+            return 0.0;
+        }
+
+        open_slots(name) as f64 / total as f64
+    }
+
+    /// Checks a requested display color, returning it in canonical form if
+    /// it is valid and nobody except the player at `index` already has it.
+    fn claim_color(&self, color: &str, index: Option<usize>) -> Result<String, ApiError> {
+        let color = parse_color(color).ok_or_else(|| {
+            ApiError::new(Status::UnprocessableEntity, "invalid color").with_details(
+                json!({ "allowed": "#rgb, #rrggbb or a named color", "named": NAMED_COLORS }),
+            )
+        })?;
+
+        let holder = self
+            .players
+            .iter()
+            .enumerate()
+            .find(|(i, p)| Some(*i) != index && p.color.as_ref() == Some(&color));
+        if let Some((_, holder)) = holder {
+            return Err(ApiError::new(Status::Conflict, "color already taken")
+                .with_details(json!({ "color": color, "taken_by": holder.name })));
+        }
+
+        Ok(color)
+    }
+
+    /// The first suspect in the deck not already played by someone in the
+    /// lobby.
+    fn free_character(&self) -> Option<Suspect> {
+        self.settings
+            .deck
+            .suspects
+            .iter()
+            .find(|s| !self.players.iter().any(|p| p.character.as_ref() == Some(s)))
+            .cloned()
+    }
+
+    fn token_index(&self, token: &PlayerToken) -> Option<usize> {
+        self.players.iter().position(|p| p.token == token.0)
+    }
+
+    /// The suggestions as `viewer` may see them, with each turn on which
+    /// the active player declined to suggest placed where it happened.
+    fn history_view(&self, viewer: Option<&str>, is_admin: bool) -> Vec<HistoryEntry<'_>> {
+        let mut declined = self.declined_suggestions.iter().peekable();
+        let mut entries = Vec::new();
+
+        for (index, record) in self.history.iter().enumerate() {
+            while let Some(decline) = declined.next_if(|decline| decline.after <= index) {
+                entries.push(decline.entry());
+            }
+            let open = is_admin || self.shown_publicly(index);
+            entries.push(HistoryEntry::Suggestion(record.public(viewer, open)));
+        }
+        entries.extend(declined.map(DeclinedSuggestion::entry));
+
+        entries
+    }
+
+    /// The game as `viewer`, a player name, may see it. Admins see
+    /// everything; players also see their own hand and notebook; anyone
+    /// sees the public record, and the solution once the game is over.
+    fn view_for(&self, viewer: Option<&str>, is_admin: bool) -> PublicGameState<'_> {
+        let private = |player: &Player| is_admin || viewer == Some(player.name.as_str());
+
+        PublicGameState {
+            phase: self.phase,
+            host: &self.host,
+            paused: self.paused,
+            theme: &self.settings.theme,
+            turn_number: self.turn_number,
+            active_player: self
+                .active_player()
+                .filter(|_| self.phase == Phase::InProgress)
+                .map(|index| self.players[index].name.as_str()),
+            players: self
+                .players
+                .iter()
+                .map(|player| PlayerView {
+                    name: &player.name,
+                    character: player.character.as_ref(),
+                    color: player.color.as_deref(),
+                    location: player.location.as_ref(),
+                    eliminated: player.eliminated,
+                    eliminated_reason: player.eliminated_reason.as_ref(),
+                    hand_size: player.cards.len(),
+                    cards: private(player).then_some(player.cards.as_slice()),
+                    notebook: private(player).then_some(player.notebook.as_slice()),
+                    intrigue_cards: private(player).then_some(player.intrigue_cards.as_slice()),
+                })
+                .collect(),
+            history: self.history_view(viewer, is_admin),
+            intrigue_played: &self.intrigue_played,
+            winner: self.winner.as_deref(),
+            timed_out: self.timed_out,
+            action_seq: self.action_seq,
+            starting_hint: self.starting_hint.as_ref(),
+            set_aside: &self.set_aside,
+            solution: self
+                .solution
+                .as_ref()
+                .filter(|_| is_admin || self.phase == Phase::Finished),
+        }
+    }
+
+    /// The name behind an optional bearer token; anonymous when it is
+    /// missing or unknown.
+    fn viewer_name(&self, token: Option<&PlayerToken>) -> Option<&str> {
+        token
+            .and_then(|token| self.token_index(token))
+            .map(|index| self.players[index].name.as_str())
+    }
+
+    fn is_host(&self, token: &PlayerToken) -> bool {
+        self.token_index(token)
+            .is_some_and(|index| self.players[index].name == self.host)
+    }
+}
+
+/// How long clients should wait before retrying against a paused game.
+const PAUSED_RETRY_SECS: u64 = 5;
+
+const MIN_PLAYERS: usize = 2;
+const MAX_PLAYERS: usize = 6;
+
+/// Artwork sets a frontend may pick from.
+const THEMES: [&str; 3] = ["classic", "vintage", "modern"];
+
+/// Options the host picks when starting a game. Unknown keys are refused
+/// rather than dropped, so a misspelled house rule doesn't go unnoticed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GameSettings {
+    /// Purely descriptive; echoed back so clients can choose assets.
+    theme: String,
+    disproof_direction: DisproofDirection,
+    disproof_mode: DisproofMode,
+    deck: Deck,
+    /// Rooms joined by secret passages; the classic diagonals by default.
+    secret_passages: Vec<Passage>,
+    /// Steps a secret passage counts for when measuring distances.
+    passage_steps: u32,
+    /// Starts the game as soon as this many players have joined.
+    auto_start_at: Option<usize>,
+    /// Seconds a player gets before their turn passes to the next seat.
+    turn_timer_secs: Option<u64>,
+    /// Seconds after starting that the game ends undecided.
+    max_duration_secs: Option<u64>,
+    /// Seconds a finished game stays up for review, read-only, before it
+    /// is cleared away. Without it, a finished game stays until
+    /// someone deletes it.
+    finished_retention_secs: Option<u64>,
+    /// Shows every disproof's card in the public history, not only to the
+    /// suggester.
+    open_info: bool,
+    /// Teaching variant: shows just the game's first disproof to everyone.
+    open_first_disproof: bool,
+    /// Seconds a disprover gets to pick a card before one is shown for them.
+    disproof_timeout_secs: Option<u64>,
+    /// Which card is shown for a disprover who ran out of time.
+    disproof_timeout_pick: DisproofPick,
+    /// House rule barring players from naming their own character.
+    forbid_self_suspect: bool,
+    /// Announces what a wrong accusation named, not only that it was wrong.
+    announce_wrong_accusation: bool,
+    /// Fast variant: a suggestion must name the room moved into this turn.
+    require_move_before_suggest: bool,
+    /// Learning mode, unlocking hints such as hypothetical suggestions.
+    practice: bool,
+    /// Tells the suggester which of the cards they named nobody holds.
+    suggestion_analytics: bool,
+    /// Seeds the choice of solution; picked at random when not given.
+    solution_seed: Option<u64>,
+    /// Seeds the shuffle of the dealt cards, independently of the solution.
+    deal_seed: Option<u64>,
+    /// Shows everyone one dealt card at the start, for beginners.
+    starting_hint: bool,
+    /// Cuts the shuffled cards at a point drawn from the same seed before
+    /// dealing, as a table would.
+    cut_deck: bool,
+    /// Seeds the dice, together with the turn number.
+    dice_seed: Option<u64>,
+    /// Exactly which cards each player gets, for reproducing a game; the
+    /// cards left over go in the envelope. Only admins may set it, when
+    /// starting the game, and it is never shown.
+    #[serde(skip)]
+    fixed_hands: Option<BTreeMap<String, Vec<Card>>>,
+    /// Seats the players in a shuffled order rather than the order they
+    /// joined in.
+    randomize_turn_order: bool,
+    /// Seeds the seating shuffle.
+    seating_seed: Option<u64>,
+}
+
+/// One line of `GET /game/rules`.
+#[derive(Debug, Serialize)]
+struct Rule {
+    setting: &'static str,
+    summary: String,
+}
+
+impl Rule {
+    fn new(setting: &'static str, summary: impl Into<String>) -> Self {
+        Self {
+            setting,
+            summary: summary.into(),
+        }
+    }
+}
+
+/// Named bundles of settings for common ways to play.
+const PRESETS: [&str; 4] = ["standard", "fast", "open-info", "quick"];
+
+/// Presets that are listed but can't be played yet, with the reason why.
+const UNAVAILABLE_PRESETS: [(&str, &str); 1] = [("team", "team play is not supported yet")];
+
+/// A deck and board registered under a name, to set games up with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Edition {
+    deck: Deck,
+    #[serde(default = "board::classic_passages")]
+    secret_passages: Vec<Passage>,
+}
+
+#[derive(Deserialize)]
+struct EditionRequest {
+    name: String,
+    #[serde(flatten)]
+    edition: Edition,
+}
+
+/// The editions registered with `POST /editions`. Settings copy the deck
+/// and board out of an edition, so games never look back at the registry.
+#[derive(Default)]
+struct Editions(Mutex<BTreeMap<String, Edition>>);
+
+impl Editions {
+    fn registry(&self) -> MutexGuard<'_, BTreeMap<String, Edition>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn get(&self, name: &str) -> Option<Edition> {
+        self.registry().get(name).cloned()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.registry().keys().cloned().collect()
+    }
+}
+
+/// Which way round the table the search for a disproof goes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+enum DisproofDirection {
+    #[default]
+    Clockwise,
+    Counterclockwise,
+}
+
+/// Who picks the card a disprover shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DisproofMode {
+    /// The server shows the disprover's first matching card straight away.
+    #[default]
+    Automatic,
+    /// The suggestion waits until the disprover reveals a card of their choice.
+    Choose,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DisproofPick {
+    /// Any of the disprover's matching cards.
+    #[default]
+    Random,
+    /// The matching card that comes first in the deck.
+    Lowest,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            theme: THEMES[0].to_owned(),
+            disproof_direction: DisproofDirection::default(),
+            disproof_mode: DisproofMode::default(),
+            deck: Deck::default(),
+            secret_passages: board::classic_passages(),
+            passage_steps: 0,
+            auto_start_at: None,
+            turn_timer_secs: None,
+            max_duration_secs: None,
+            finished_retention_secs: None,
+            open_info: false,
+            open_first_disproof: false,
+            disproof_timeout_secs: None,
+            disproof_timeout_pick: DisproofPick::default(),
+            forbid_self_suspect: false,
+            announce_wrong_accusation: true,
+            require_move_before_suggest: false,
+            practice: false,
+            suggestion_analytics: false,
+            solution_seed: None,
+            deal_seed: None,
+            starting_hint: false,
+            cut_deck: false,
+            dice_seed: None,
+            fixed_hands: None,
+            randomize_turn_order: false,
+            seating_seed: None,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Fills in random seeds where none were given. They are kept either way,
+    /// so any game can be replayed; `POST /game` echoes them to admins.
+    fn pick_seeds(&mut self) {
+        self.solution_seed.get_or_insert_with(|| rng().random());
+        self.deal_seed.get_or_insert_with(|| rng().random());
+        self.dice_seed.get_or_insert_with(|| rng().random());
+        self.seating_seed.get_or_insert_with(|| rng().random());
+    }
+
+    fn preset(name: &str) -> Option<Self> {
+        let standard = Self::default();
+
+        match name {
+            "standard" => Some(standard),
+            "fast" => Some(Self {
+                turn_timer_secs: Some(60),
+                disproof_timeout_secs: Some(15),
+                require_move_before_suggest: true,
+                ..standard
+            }),
+            "open-info" => Some(Self {
+                open_info: true,
+                ..standard
+            }),
+            // Just the four corner rooms, which the secret passages join.
+            "quick" => Some(Self {
+                deck: Deck {
+                    rooms: vec![Room::Kitchen, Room::Lounge, Room::Conservatory, Room::Study],
+                    ..Deck::default()
+                },
+                ..standard
+            }),
+            _ => None,
+        }
+    }
+
+    /// The rules these settings play by, in words: the basics always, then
+    /// every house rule and variant that is switched on.
+    fn rules(&self) -> Vec<Rule> {
+        let mut rules = vec![
+            Rule::new(
+                "deck",
+                format!(
+                    "{} suspects, {} weapons and {} rooms{}",
+                    self.deck.suspects.len(),
+                    self.deck.weapons.len(),
+                    self.deck.rooms.len(),
+                    match self.deck.clocks {
+                        0 => String::new(),
+                        clocks => format!(", plus {clocks} clock cards"),
+                    }
+                ),
+            ),
+            Rule::new(
+                "disproof_direction",
+                format!(
+                    "disproofs go round {} from the suggester",
+                    match self.disproof_direction {
+                        DisproofDirection::Clockwise => "clockwise",
+                        DisproofDirection::Counterclockwise => "counterclockwise",
+                    }
+                ),
+            ),
+            Rule::new(
+                "disproof_mode",
+                match self.disproof_mode {
+                    DisproofMode::Automatic => "the first matching card is shown automatically",
+                    DisproofMode::Choose => "disprovers choose which matching card to show",
+                },
+            ),
+            Rule::new(
+                "secret_passages",
+                match self.secret_passages.len() {
+                    0 => "no secret passages".to_owned(),
+                    _ => format!(
+                        "secret passages join {}",
+                        self.secret_passages
+                            .iter()
+                            .map(|(a, b)| format!("{a:?} and {b:?}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                },
+            ),
+        ];
+
+        if !self.deck.intrigue.is_empty() {
+            rules.push(Rule::new(
+                "intrigue",
+                format!("{} keeper cards are dealt", self.deck.intrigue.len()),
+            ));
+        }
+        if self.deck.set_aside > 0 {
+            rules.push(Rule::new(
+                "set_aside",
+                format!(
+                    "{} cards are set aside face up instead of being dealt",
+                    self.deck.set_aside
+                ),
+            ));
+        }
+        if let Some(secs) = self.turn_timer_secs {
+            rules.push(Rule::new(
+                "turn_timer_secs",
+                format!("a turn passes on after {secs} seconds"),
+            ));
+        }
+        if let Some(secs) = self.max_duration_secs {
+            rules.push(Rule::new(
+                "max_duration_secs",
+                format!("the game ends undecided after {secs} seconds"),
+            ));
+        }
+        if let Some(secs) = self.finished_retention_secs {
+            rules.push(Rule::new(
+                "finished_retention_secs",
+                format!("a finished game is kept for {secs} seconds, then cleared"),
+            ));
+        }
+        if let Some(secs) = self.disproof_timeout_secs {
+            rules.push(Rule::new(
+                "disproof_timeout_secs",
+                format!(
+                    "a disprover who hasn't chosen within {secs} seconds shows {} matching card",
+                    match self.disproof_timeout_pick {
+                        DisproofPick::Random => "a random",
+                        DisproofPick::Lowest => "their first",
+                    }
+                ),
+            ));
+        }
+        if self.open_info {
+            rules.push(Rule::new(
+                "open_info",
+                "every card shown is shown to everyone",
+            ));
+        } else if self.open_first_disproof {
+            rules.push(Rule::new(
+                "open_first_disproof",
+                "the first card shown is shown to everyone",
+            ));
+        }
+        if self.forbid_self_suspect {
+            rules.push(Rule::new(
+                "forbid_self_suspect",
+                "players may not suggest their own character",
+            ));
+        }
+        if !self.announce_wrong_accusation {
+            rules.push(Rule::new(
+                "announce_wrong_accusation",
+                "a wrong accusation's guess is kept secret",
+            ));
+        }
+        if self.require_move_before_suggest {
+            rules.push(Rule::new(
+                "require_move_before_suggest",
+                "suggestions must name the room just moved into",
+            ));
+        }
+        if self.cut_deck {
+            rules.push(Rule::new("cut_deck", "the deck is cut before dealing"));
+        }
+        if self.starting_hint {
+            rules.push(Rule::new(
+                "starting_hint",
+                "one dealt card is shown to everyone at the start",
+            ));
+        }
+        if self.randomize_turn_order {
+            rules.push(Rule::new(
+                "randomize_turn_order",
+                "players are seated in a shuffled order",
+            ));
+        }
+        if self.practice {
+            rules.push(Rule::new("practice", "practice hints are available"));
+        }
+        if self.suggestion_analytics {
+            rules.push(Rule::new(
+                "suggestion_analytics",
+                "suggesters learn which named cards nobody holds",
+            ));
+        }
+        if let Some(count) = self.auto_start_at {
+            rules.push(Rule::new(
+                "auto_start_at",
+                format!("the game starts once {count} players have joined"),
+            ));
+        }
+
+        rules
+    }
+
+    /// The envelope `fixed_hands` leaves, if set, or what is wrong with it:
+    /// every seated player needs a hand, no card may be dealt twice, and the
+    /// cards left over must be one suspect, one weapon and one room.
+    fn fixed_solution(&self, players: &[Player]) -> Result<Option<Suggestion>, Vec<String>> {
+        let Some(hands) = &self.fixed_hands else {
+            return Ok(None);
+        };
+
+        let mut problems = Vec::new();
+        for name in hands.keys() {
+            if !players.iter().any(|player| &player.name == name) {
+                problems.push(format!("{name} is not playing"));
+            }
+        }
+        for player in players {
+            if !hands.contains_key(&player.name) {
+                problems.push(format!("no hand given for {}", player.name));
+            }
+        }
+
+        let deck = self.deck.cards();
+        let mut dealt: Vec<&Card> = Vec::new();
+        for card in hands.values().flatten() {
+            if !deck.contains(card) {
+                problems.push(format!("{} is not in the deck", card.name()));
+            } else if dealt.contains(&card) {
+                problems.push(format!("{} is dealt twice", card.name()));
+            }
+            dealt.push(card);
+        }
+
+        let left: Vec<&Card> = deck.iter().filter(|card| !dealt.contains(card)).collect();
+        let solution = match left.as_slice() {
+            [
+                Card::Suspect(suspect),
+                Card::Weapon(weapon),
+                Card::Room(room),
+            ] => Some(Suggestion {
+                suspect: suspect.clone(),
+                weapon: weapon.clone(),
+                room: room.clone(),
+            }),
+            _ => {
+                let names: Vec<String> = left.iter().map(|card| card.name()).collect();
+                problems.push(format!(
+                    "left for the envelope: {}; expected one suspect, weapon and room",
+                    names.join(", ")
+                ));
+                None
+            }
+        };
+
+        match solution {
+            Some(solution) if problems.is_empty() => Ok(Some(solution)),
+            _ => Err(problems),
+        }
+    }
+
+    fn hands_error(problems: Vec<String>) -> ApiError {
+        ApiError::new(
+            Status::UnprocessableEntity,
+            "hands don't partition the deck",
+        )
+        .with_details(json!({ "problems": problems }))
+    }
+
+    /// Settings from a request body: a `preset` and an `edition` by name,
+    /// then any settings given alongside them on top.
+    fn from_body(body: Option<Value>, editions: &Editions) -> Result<Self, ApiError> {
+        let mut body = match body {
+            None => Map::new(),
+            Some(Value::Object(body)) => body,
+            Some(_) => {
+                return Err(ApiError::new(
+                    Status::UnprocessableEntity,
+                    "settings must be an object",
+                ));
+            }
+        };
+
+        let mut settings = match body.remove("preset") {
+            None => Self::default(),
+            Some(Value::String(name)) => Self::preset(&name).ok_or_else(|| {
+                match UNAVAILABLE_PRESETS
+                    .iter()
+                    .find(|(unavailable, _)| *unavailable == name)
+                {
+                    Some((_, reason)) => {
+                        ApiError::new(Status::UnprocessableEntity, "preset not available")
+                            .with_details(json!({ "preset": name, "reason": reason }))
+                    }
+                    None => ApiError::new(Status::UnprocessableEntity, "unknown preset")
+                        .with_details(json!({ "allowed": PRESETS })),
+                }
+            })?,
+            Some(_) => {
+                return Err(ApiError::new(
+                    Status::UnprocessableEntity,
+                    "preset must be a name",
+                ));
+            }
+        };
+
+        match body.remove("edition") {
+            None => (),
+            Some(Value::String(name)) => {
+                let edition = editions.get(&name).ok_or_else(|| {
+                    ApiError::new(Status::UnprocessableEntity, "unknown edition")
+                        .with_details(json!({ "edition": name, "allowed": editions.names() }))
+                })?;
+                settings.deck = edition.deck;
+                settings.secret_passages = edition.secret_passages;
+            }
+            Some(_) => {
+                return Err(ApiError::new(
+                    Status::UnprocessableEntity,
+                    "edition must be a name",
+                ));
+            }
+        }
+
+        let Value::Object(mut merged) = serde_json::to_value(settings).unwrap() else {
+            unreachable!("settings serialize as an object");
+        };
+        merged.extend(body);
+
+        serde_json::from_value(Value::Object(merged)).map_err(|e| {
+            ApiError::new(Status::UnprocessableEntity, "invalid settings")
+                .with_details(json!({ "reason": e.to_string() }))
+        })
+    }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        match self.errors().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Everything that rules these settings out, in the order `validate`
+    /// reports them.
+    fn errors(&self) -> Vec<ApiError> {
+        let mut errors = Vec::new();
+
+        if !THEMES.contains(&self.theme.as_str()) {
+            errors.push(
+                ApiError::new(Status::UnprocessableEntity, "unknown theme")
+                    .with_details(json!({ "allowed": THEMES })),
+            );
+        }
+
+        if let Some(count) = self.auto_start_at
+            && !(MIN_PLAYERS..=MAX_PLAYERS).contains(&count)
+        {
+            errors.push(
+                ApiError::new(Status::UnprocessableEntity, "invalid auto_start_at").with_details(
+                    json!({ "min": MIN_PLAYERS, "max": MAX_PLAYERS, "given": count }),
+                ),
+            );
+        }
+
+        let problems = self.deck.problems();
+        if !problems.is_empty() {
+            errors.push(
+                ApiError::new(Status::UnprocessableEntity, "invalid deck")
+                    .with_details(json!({ "problems": problems })),
+            );
+        }
+
+        let problems = board::passage_problems(&self.secret_passages);
+        if !problems.is_empty() {
+            errors.push(
+                ApiError::new(Status::UnprocessableEntity, "invalid secret passages")
+                    .with_details(json!({ "problems": problems })),
+            );
+        }
+
+        errors
+    }
+
+    /// Things that don't stop a game with `players` players but may not
+    /// be what the host meant.
+    fn warnings(&self, players: usize) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        let dealt = self
+            .deck
+            .cards()
+            .len()
+            .saturating_sub(3 + usize::from(self.deck.set_aside));
+        if players > 0 && !dealt.is_multiple_of(players) {
+            warnings.push(format!(
+                "{dealt} cards don't divide among {players} players: hands of {} and {}",
+                dealt / players,
+                dealt / players + 1
+            ));
+        }
+
+        for (name, seed) in [
+            ("solution_seed", self.solution_seed),
+            ("deal_seed", self.deal_seed),
+            ("dice_seed", self.dice_seed),
+            (
+                "seating_seed",
+                self.seating_seed.filter(|_| self.randomize_turn_order),
+            ),
+        ] {
+            if seed.is_some() {
+                warnings.push(format!("{name} is fixed, so every game repeats it"));
+            }
+        }
+
+        warnings
+    }
+}
+
+/// The cards a game is played with, standard or a custom selection.
+///
+/// However big the deck, the envelope always holds exactly one suspect, one
+/// weapon and one room, so an accusation is always checked against three
+/// cards; everything else is dealt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Deck {
+    suspects: Vec<Suspect>,
+    weapons: Vec<Weapon>,
+    rooms: Vec<Room>,
+    /// Bonus clock cards from the Master Detective edition, numbered from 1.
+    /// They are dealt like any other card but never go in the envelope.
+    clocks: u8,
+    /// Keeper cards, also from Master Detective. Dealt out separately and
+    /// played for their effect; they are never clues.
+    intrigue: Vec<Keeper>,
+    /// Cards laid face up on the table from the end of the shuffled deck
+    /// instead of being dealt, for decks too big to play in full or that
+    /// don't divide evenly. Everyone sees them, so they are never clues.
+    set_aside: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Keeper {
+    /// Take another turn after this one.
+    AnotherTurn,
+    /// Move to any room.
+    MoveAnywhere,
+    /// Look at one card from any player's hand.
+    SeeACard,
+    /// Make a suggestion without entering a room.
+    SuggestAnywhere,
+}
+
+/// Master Detective has eight; homemade decks may add a few more.
+const MAX_CLOCK_CARDS: u8 = 12;
+
+impl Default for Deck {
+    fn default() -> Self {
+        Self {
+            suspects: Suspect::iter().collect(),
+            weapons: Weapon::iter().collect(),
+            rooms: Room::iter().collect(),
+            clocks: 0,
+            intrigue: Vec::new(),
+            set_aside: 0,
+        }
+    }
+}
+
+impl Deck {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (category, len, duplicated) in [
+            (
+                "suspects",
+                self.suspects.len(),
+                has_duplicates(&self.suspects),
+            ),
+            ("weapons", self.weapons.len(), has_duplicates(&self.weapons)),
+            ("rooms", self.rooms.len(), has_duplicates(&self.rooms)),
+        ] {
+            if len == 0 {
+                problems.push(format!("no {category} to choose a solution from"));
+            }
+            if duplicated {
+                problems.push(format!("{category} contain duplicates"));
+            }
+        }
+
+        if self.clocks > MAX_CLOCK_CARDS {
+            problems.push(format!("at most {MAX_CLOCK_CARDS} clock cards"));
+        }
+
+        let left = self.cards().len().saturating_sub(3);
+        if usize::from(self.set_aside) > left {
+            problems.push(format!(
+                "only {left} cards are left after the envelope to set aside"
+            ));
+        }
+
+        problems
+    }
+
+    fn cards(&self) -> Vec<Card> {
+        let mut cards = Vec::new();
+
+        cards.extend(self.suspects.iter().cloned().map(Card::Suspect));
+        cards.extend(self.weapons.iter().cloned().map(Card::Weapon));
+        cards.extend(self.rooms.iter().cloned().map(Card::Room));
+        cards.extend((1..=self.clocks).map(Card::Clock));
+
+        cards
+    }
+
+    /// Picks the envelope. Only call on a deck without `problems`.
+    fn draw_solution(&self, rng: &mut impl Rng) -> Suggestion {
+        Suggestion {
+            suspect: self.suspects.choose(rng).unwrap().clone(),
+            weapon: self.weapons.choose(rng).unwrap().clone(),
+            room: self.rooms.choose(rng).unwrap().clone(),
+        }
+    }
+}
+
+fn has_duplicates<T: PartialEq>(items: &[T]) -> bool {
+    items
+        .iter()
+        .enumerate()
+        .any(|(index, item)| items[..index].contains(item))
+}
+
+/// The game as one viewer may see it; built by `GameState::view_for`.
+#[derive(Debug, Serialize)]
+struct PublicGameState<'a> {
+    phase: Phase,
+    host: &'a str,
+    paused: bool,
+    theme: &'a str,
+    turn_number: u32,
+    active_player: Option<&'a str>,
+    players: Vec<PlayerView<'a>>,
+    history: Vec<HistoryEntry<'a>>,
+    intrigue_played: &'a [IntriguePlay],
+    winner: Option<&'a str>,
+    timed_out: bool,
+    action_seq: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starting_hint: Option<&'a Card>,
+    #[serde(skip_serializing_if = "<[Card]>::is_empty")]
+    set_aside: &'a [Card],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution: Option<&'a Suggestion>,
+}
+
+/// Just enough to list the game: nothing about hands, the solution or
+/// the suggestions made.
+#[derive(Debug, Serialize)]
+struct GameSummary<'a> {
+    id: &'a str,
+    created_at: u64,
+    phase: Phase,
+    host: &'a str,
+    players: usize,
+    started_at: Option<u64>,
+    joinable: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PlayerView<'a> {
+    name: &'a str,
+    character: Option<&'a Suspect>,
+    color: Option<&'a str>,
+    location: Option<&'a Position>,
+    eliminated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eliminated_reason: Option<&'a EliminationReason>,
+    hand_size: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cards: Option<&'a [Card]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notebook: Option<&'a [NotebookMark]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intrigue_cards: Option<&'a [Keeper]>,
+}
+
+/// How evenly the cards were spread across the dealt hands.
+#[derive(Debug, Serialize)]
+struct DealFairness {
+    min_hand_size: usize,
+    max_hand_size: usize,
+    even: bool,
+}
+
+impl DealFairness {
+    fn of(players: &[Player]) -> Self {
+        let sizes = players.iter().map(|p| p.cards.len());
+        let min_hand_size = sizes.clone().min().unwrap_or(0);
+        let max_hand_size = sizes.max().unwrap_or(0);
+
+        Self {
+            min_hand_size,
+            max_hand_size,
+            even: min_hand_size == max_hand_size,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedGame<'a> {
+    #[serde(flatten)]
+    state: PublicGameState<'a>,
+    fairness: DealFairness,
+    /// The seeds the game was dealt from, for admins only: together they
+    /// give the solution away.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution_seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deal_seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Phase {
+    Lobby,
+    InProgress,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromForm)]
+struct Suggestion {
+    suspect: Suspect,
+    weapon: Weapon,
+    room: Room,
+}
+
+impl Suggestion {
+    fn as_cards(&self) -> [Card; 3] {
+        [
+            Card::Suspect(self.suspect.clone()),
+            Card::Weapon(self.weapon.clone()),
+            Card::Room(self.room.clone()),
+        ]
+    }
+}
+
+/// A suggestion as it happened, including the card only the suggester saw.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SuggestionRecord {
+    suggested_by: String,
+    suggestion: Suggestion,
+    /// Players asked before the disprover, or everyone asked if nobody
+    /// could disprove, in the order they were asked.
+    #[serde(default)]
+    passed: Vec<String>,
+    outcome: DisproofOutcome,
+}
+
+/// How a suggestion was answered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum DisproofOutcome {
+    /// `by` holds a matching card but hasn't revealed which one yet.
+    Pending { by: String },
+    /// `card` was shown to `shown_to`, the suggester, and nobody else.
+    Disproved {
+        by: String,
+        shown_to: String,
+        card: Card,
+    },
+    /// Nobody asked could show a card.
+    AllPassed,
+}
+
+impl DisproofOutcome {
+    /// Whoever answered, or has yet to.
+    fn disprover(&self) -> Option<&str> {
+        match self {
+            DisproofOutcome::Pending { by } | DisproofOutcome::Disproved { by, .. } => Some(by),
+            DisproofOutcome::AllPassed => None,
+        }
+    }
+
+    /// The outcome as `viewer` may see it: the card stays hidden from all
+    /// but its `shown_to`, unless `open_info` shows it to everyone.
+    fn public(&self, viewer: Option<&str>, open_info: bool) -> PublicOutcome<'_> {
+        match self {
+            DisproofOutcome::Pending { by } => PublicOutcome::Pending { by },
+            DisproofOutcome::Disproved { by, shown_to, card } => PublicOutcome::Disproved {
+                by,
+                shown_to,
+                card: Some(card).filter(|_| open_info || viewer == Some(shown_to.as_str())),
+            },
+            DisproofOutcome::AllPassed => PublicOutcome::AllPassed,
+        }
+    }
+}
+
+/// A `DisproofOutcome` with the card left out unless the viewer may see it.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum PublicOutcome<'a> {
+    Pending {
+        by: &'a str,
+    },
+    Disproved {
+        by: &'a str,
+        shown_to: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        card: Option<&'a Card>,
+    },
+    AllPassed,
+}
+
+/// What a player learns from a suggestion: who asked and who answered, and
+/// the card shown if it was shown to them or `open_info` is on.
+#[derive(Debug, Serialize)]
+struct PublicSuggestion<'a> {
+    suggested_by: &'a str,
+    #[serde(flatten)]
+    suggestion: &'a Suggestion,
+    outcome: PublicOutcome<'a>,
+}
+
+impl SuggestionRecord {
+    fn public(&self, viewer: Option<&str>, open_info: bool) -> PublicSuggestion<'_> {
+        PublicSuggestion {
+            suggested_by: &self.suggested_by,
+            suggestion: &self.suggestion,
+            outcome: self.outcome.public(viewer, open_info),
+        }
+    }
+}
+
+/// A turn on which the active player said they wouldn't suggest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeclinedSuggestion {
+    player: String,
+    turn: u32,
+    /// How many suggestions had been made by then, which places it among
+    /// them in the history.
+    after: usize,
+}
+
+impl DeclinedSuggestion {
+    fn entry(&self) -> HistoryEntry<'_> {
+        HistoryEntry::NoSuggestion {
+            no_suggestion_by: &self.player,
+            turn: self.turn,
+        }
+    }
+}
+
+/// A line of the history: a suggestion, or a turn that went without one.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum HistoryEntry<'a> {
+    Suggestion(PublicSuggestion<'a>),
+    NoSuggestion {
+        no_suggestion_by: &'a str,
+        turn: u32,
+    },
+}
+
+impl<'a> HistoryEntry<'a> {
+    fn suggestion(self) -> Option<PublicSuggestion<'a>> {
+        match self {
+            HistoryEntry::Suggestion(suggestion) => Some(suggestion),
+            HistoryEntry::NoSuggestion { .. } => None,
+        }
+    }
+}
+
+/// The private answer to a suggestion, sent only to the suggester.
+#[derive(Debug, Serialize)]
+struct SuggestionResult<'a> {
+    suggested_by: &'a str,
+    outcome: &'a DisproofOutcome,
+    /// Advisory only: the same trio was already suggested this game.
+    duplicate: bool,
+    /// With `suggestion_analytics`, the suggested cards nobody holds, which
+    /// can only be in the envelope.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unheld: Option<Vec<Card>>,
+}
+
+/// The order a hand is listed in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, FromFormField)]
+enum HandSort {
+    /// As the cards were dealt.
+    #[default]
+    Deal,
+    /// Grouped suspects, weapons, then rooms.
+    Category,
+}
+
+/// How `GET /game/notebook/<name>/export` writes the notebook out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, FromFormField)]
+enum NotebookFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// One row of an exported notebook: a card of the deck in play.
+#[derive(Debug, Serialize)]
+struct NotebookRow<'a> {
+    card: String,
+    category: &'static str,
+    mark: Option<&'static str>,
+    held_by: Option<&'a str>,
+    seen: bool,
+}
+
+impl NotebookRow<'_> {
+    const CSV_HEADER: &'static str = "card,category,mark,held_by,seen";
+
+    fn to_csv(&self) -> String {
+        [
+            csv_field(&self.card),
+            self.category.to_owned(),
+            self.mark.unwrap_or_default().to_owned(),
+            csv_field(self.held_by.unwrap_or_default()),
+            self.seen.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+/// Quotes a CSV field when it would otherwise break the row.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[derive(Deserialize)]
+struct RevealRequest {
+    card: Card,
+}
+
+#[derive(Deserialize)]
+struct IntrigueRequest {
+    card: Keeper,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Player {
+    name: String,
+    cards: Vec<Card>,
+    character: Option<Suspect>,
+    /// Display color, independent of the character played.
+    color: Option<String>,
+    /// Where the player's token stands, once the game has placed it.
+    location: Option<Position>,
+    /// Out of the game after a wrong accusation or resigning, though still
+    /// showing cards.
+    eliminated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eliminated_reason: Option<EliminationReason>,
+    #[serde(default)]
+    intrigue_cards: Vec<Keeper>,
+    #[serde(skip)]
+    token: String,
+    /// The player's own deduction notes, shown to nobody else. The game
+    /// counts them as what the player knows, and the starting hint is
+    /// written here.
+    #[serde(default)]
+    notebook: Vec<NotebookMark>,
+}
+
+impl Player {
+    fn new(name: &str, character: Option<Suspect>) -> Self {
+        Self {
+            name: name.to_owned(),
+            cards: Vec::<Card>::new(),
+            character,
+            color: None,
+            location: None,
+            eliminated: false,
+            eliminated_reason: None,
+            intrigue_cards: Vec::new(),
+            token: format!("{:032x}", rng().random::<u128>()),
+            notebook: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NotebookMark {
+    card: Card,
+    mark: Mark,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mark {
+    /// In the player's own hand.
+    Have,
+    /// Known to be held by the named player.
+    HeldBy(String),
+    Maybe,
+}
+
+#[derive(Deserialize)]
+struct MarkRequest {
+    card: Card,
+    /// `null` clears the card's mark.
+    mark: Option<Mark>,
+}
+
+const NAMED_COLORS: [&str; 8] = [
+    "red", "orange", "yellow", "green", "blue", "purple", "white", "black",
+];
+
+/// Accepts `#rgb`, `#rrggbb` or one of `NAMED_COLORS`, case-insensitively,
+/// and returns it lowercased.
+fn parse_color(input: &str) -> Option<String> {
+    let color = input.trim().to_ascii_lowercase();
+
+    let valid = match color.strip_prefix('#') {
+        Some(hex) => matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => NAMED_COLORS.contains(&color.as_str()),
+    };
+
+    valid.then_some(color)
+}
+
+/// Why `name` can't be a player's name, if it can't. Names live in the
+/// path as `/players/<name>`, so they must come back from it unchanged:
+/// nothing that splits a path, starts a query or fragment, or would be
+/// percent-decoded twice.
+fn name_problem(name: &str) -> Option<&'static str> {
+    if name.trim().is_empty() {
+        Some("name is empty")
+    } else if name == "." || name == ".." {
+        Some("name is a path step")
+    } else if name.contains(['/', '\\']) {
+        Some("name contains a path separator")
+    } else if name.contains(['%', '?', '#']) {
+        Some("name contains a character with a meaning in URLs")
+    } else if name.chars().any(char::is_control) {
+        Some("name contains a control character")
+    } else {
+        None
+    }
+}
+
+/// The key two player names are compared by: case-folded, with accents
+/// stripped, so "José" and "jose" count as the same name.
+fn name_key(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Operator settings. Admin routes are refused unless `admin_token` is set.
+struct AdminConfig {
+    token: Option<String>,
+    /// Lets admins see the solution of a game as they delete it, for
+    /// checking deals during development. Off unless configured.
+    reveal_on_delete: bool,
+}
+
+/// Proof the request came from an operator: an `X-Admin-Token` header
+/// matching the configured `admin_token`.
+struct AdminToken;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let expected = request
+            .rocket()
+            .state::<AdminConfig>()
+            .and_then(|config| config.token.as_deref());
+
+        match (request.headers().get_one("X-Admin-Token"), expected) {
+            (None, _) => Outcome::Error((Status::Unauthorized, ())),
+            (Some(given), Some(expected)) if given == expected => Outcome::Success(AdminToken),
+            (Some(_), _) => Outcome::Error((Status::Forbidden, ())),
+        }
+    }
+}
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything the server holds, for backups. Tokens travel separately,
+/// keyed by player name, since players never serialize them.
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    game: GameState,
+    tokens: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.version != SNAPSHOT_VERSION {
+            problems.push(format!(
+                "snapshot version {} is not {SNAPSHOT_VERSION}",
+                self.version
+            ));
+        }
+
+        let players = &self.game.players;
+        for (index, player) in players.iter().enumerate() {
+            if !self.tokens.contains_key(&player.name) {
+                problems.push(format!("no token for {}", player.name));
+            }
+            if players[..index]
+                .iter()
+                .any(|p| name_key(&p.name) == name_key(&player.name))
+            {
+                problems.push(format!("{} appears more than once", player.name));
+            }
+        }
+
+        if !self.game.host.is_empty() && !players.iter().any(|p| p.name == self.game.host) {
+            problems.push(format!("host {} is not a player", self.game.host));
+        }
+
+        problems
+    }
+}
+
+/// Token sent by a player as `Authorization: Bearer <token>`.
+struct PlayerToken(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PlayerToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("Bearer "))
+        {
+            Some(token) => Outcome::Success(PlayerToken(token.to_owned())),
+            None => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// The player whose turn it is, identified by their bearer token.
+///
+/// The guard only reads the headers, so it never waits on the game from
+/// the async executor. Handlers resolve the player with `index` under
+/// their own lock, which is also where everyone else is turned away.
+///
+/// Clients must echo the `action_seq` they last saw in an `X-Action-Seq`
+/// header; if another action has been accepted since, the request is
+/// refused rather than applied twice.
+struct ActivePlayer {
+    token: PlayerToken,
+    seq: u64,
+}
+
+impl ActivePlayer {
+    fn index(&self, state: &GameState) -> Result<usize, ApiError> {
+        state.ensure_not_paused()?;
+
+        if state.phase != Phase::InProgress {
+            return Err(ApiError::new(Status::Conflict, "game not in progress"));
+        }
+
+        let index = state
+            .token_index(&self.token)
+            .ok_or_else(|| ApiError::new(Status::Unauthorized, "unknown player token"))?;
+
+        if state.active_player() != Some(index) {
+            return Err(ApiError::new(Status::Conflict, "not your turn"));
+        }
+
+        if self.seq != state.action_seq {
+            return Err(
+                ApiError::new(Status::Conflict, "stale action sequence").with_details(json!({
+                    "expected": state.action_seq,
+                    "given": self.seq,
+                })),
+            );
+        }
+
+        Ok(index)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ActivePlayer {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = match PlayerToken::from_request(request).await {
+            Outcome::Success(token) => token,
+            Outcome::Error((status, ())) => {
+                return Outcome::Error((status, ApiError::new(status, "missing bearer token")));
+            }
+            Outcome::Forward(status) => return Outcome::Forward(status),
+        };
+
+        let seq = match request.headers().get_one("X-Action-Seq").map(str::parse) {
+            Some(Ok(seq)) => seq,
+            None => {
+                let error = ApiError::new(Status::PreconditionRequired, "missing X-Action-Seq");
+                return Outcome::Error((error.status, error));
+            }
+            Some(Err(_)) => {
+                let error = ApiError::new(Status::BadRequest, "X-Action-Seq is not a number");
+                return Outcome::Error((error.status, error));
+            }
+        };
+
+        Outcome::Success(ActivePlayer { token, seq })
+    }
+}
+
+/// Body encoding picked from the `Accept` header: MessagePack when the client
+/// lists it, JSON otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Encoding {
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    fn encode<T: Serialize>(&self, value: &T) -> (ContentType, Vec<u8>) {
+        match self {
+            Encoding::Json => (ContentType::JSON, serde_json::to_vec(value).unwrap()),
+            Encoding::MessagePack => (
+                ContentType::new("application", "msgpack"),
+                rmp_serde::to_vec_named(value).unwrap(),
+            ),
+        }
+    }
+}
+
+/// How suspects, weapons and rooms are written out, picked with `?repr=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, FromFormField)]
+enum Repr {
+    /// By variant name, as everywhere else.
+    #[default]
+    Name,
+    /// By position in the enum's declaration order, which never changes:
+    /// new variants only ever go on the end. `/cards` lists the names.
+    Index,
+}
+
+impl Repr {
+    fn encode<T: Serialize>(self, encoding: Encoding, value: &T) -> (ContentType, Vec<u8>) {
+        match self {
+            Repr::Name => encoding.encode(value),
+            Repr::Index => {
+                let mut value = to_value(value).unwrap();
+                index_card_names(&mut value);
+                encoding.encode(&value)
+            }
+        }
+    }
+}
+
+/// Replaces every suspect, weapon and room name, recognized by the field
+/// or card variant it appears under, with its index.
+fn index_card_names(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                match field.as_str().and_then(|name| card_index(key, name)) {
+                    Some(index) => *field = index.into(),
+                    None => index_card_names(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(index_card_names),
+        _ => (),
+    }
+}
+
+fn card_index(key: &str, name: &str) -> Option<usize> {
+    match key {
+        "suspect" | "Suspect" => {
+            let suspect: Suspect = name.parse().ok()?;
+            Suspect::iter().position(|s| s == suspect)
+        }
+        "weapon" | "Weapon" => {
+            let weapon: Weapon = name.parse().ok()?;
+            Weapon::iter().position(|w| w == weapon)
+        }
+        "room" | "Room" => {
+            let room: Room = name.parse().ok()?;
+            Room::iter().position(|r| r == room)
+        }
+        _ => None,
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Encoding {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let msgpack = request.accept().is_some_and(|accept| {
+            accept.media_types().any(|media| {
+                media.top() == "application"
+                    && (media.sub() == "msgpack" || media.sub() == "x-msgpack")
+            })
+        });
+
+        Outcome::Success(if msgpack {
+            Encoding::MessagePack
+        } else {
+            Encoding::Json
+        })
+    }
+}
+
+/// Error status with a JSON body explaining what went wrong.
+#[derive(Debug, Serialize)]
+struct ApiError {
+    #[serde(skip)]
+    status: Status,
+    error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Value>,
+    /// Seconds after which trying again may succeed, sent as `Retry-After`.
+    #[serde(skip)]
+    retry_after: Option<u64>,
+}
+
+impl ApiError {
+    fn new(status: Status, error: &str) -> Self {
+        Self {
+            status,
+            error: error.to_owned(),
+            details: None,
+            retry_after: None,
+        }
+    }
+
+    fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    fn with_retry_after(mut self, secs: u64) -> Self {
+        self.retry_after = Some(secs);
+        self
+    }
+}
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        Self::new(status, status.reason().unwrap_or("error"))
+    }
+}
+
+#[catch(404)]
+fn not_found(request: &Request<'_>) -> ApiError {
+    ApiError::from(Status::NotFound).with_details(json!({ "path": request.uri().path().as_str() }))
+}
+
+/// A body or query that parsed as JSON but didn't fit the route.
+#[catch(422)]
+fn unprocessable() -> ApiError {
+    ApiError::new(
+        Status::UnprocessableEntity,
+        "request didn't match the expected shape",
+    )
+}
+
+#[catch(500)]
+fn internal_error() -> ApiError {
+    ApiError::new(Status::InternalServerError, "internal server error")
+}
+
+/// Everything else a guard or Rocket itself turns away, such as a missing
+/// admin token.
+#[catch(default)]
+fn default_catcher(status: Status, _request: &Request<'_>) -> ApiError {
+    status.into()
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        let body = to_string(&self).unwrap();
+
+        let mut response = Response::build_from((ContentType::JSON, body).respond_to(request)?);
+        response.status(self.status);
+        if let Some(secs) = self.retry_after {
+            response.raw_header("Retry-After", secs.to_string());
+        }
+
+        response.ok()
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JoinResponse<'a> {
+    name: &'a str,
+    token: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostTransfer {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColorRequest {
+    color: String,
+}
+
+/// A whole turn sent at once: each step given is taken in this order.
+#[derive(Debug, Deserialize)]
+struct TurnRequest {
+    #[serde(default)]
+    roll: bool,
+    #[serde(rename = "move")]
+    movement: Option<MoveRequest>,
+    suggest: Option<Suggestion>,
+    #[serde(default)]
+    end_turn: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveRequest {
+    room: Room,
+    /// Take a secret passage from the current room instead of walking.
+    #[serde(default)]
+    secret_passage: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Card {
+    Suspect(Suspect),
+    Weapon(Weapon),
+    Room(Room),
+    /// A numbered bonus card; never part of the solution.
+    Clock(u8),
+}
+
+impl Card {
+    /// The bare name `FromStr` reads back.
+    fn name(&self) -> String {
+        match self {
+            Card::Suspect(suspect) => format!("{suspect:?}"),
+            Card::Weapon(weapon) => format!("{weapon:?}"),
+            Card::Room(room) => format!("{room:?}"),
+            Card::Clock(number) => format!("Clock{number}"),
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            Card::Suspect(_) => "suspect",
+            Card::Weapon(_) => "weapon",
+            Card::Room(_) => "room",
+            Card::Clock(_) => "clock",
+        }
+    }
+
+    /// Orders cards suspects first, then weapons, rooms and clocks, each in
+    /// catalogue order.
+    fn catalogue_key(&self) -> (u8, usize) {
+        match self {
+            Card::Suspect(suspect) => (0, Suspect::iter().position(|s| s == *suspect).unwrap()),
+            Card::Weapon(weapon) => (1, Weapon::iter().position(|w| w == *weapon).unwrap()),
+            Card::Room(room) => (2, Room::iter().position(|r| r == *room).unwrap()),
+            Card::Clock(number) => (3, usize::from(*number)),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = strum::ParseError;
+
+    /// Parses a bare card name such as `Plum`, `LeadPipe` or `Clock3`; no
+    /// name is shared between categories.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        name.parse()
+            .map(Card::Suspect)
+            .or_else(|_| name.parse().map(Card::Weapon))
+            .or_else(|_| name.parse().map(Card::Room))
+            .or_else(|_| {
+                name.strip_prefix("Clock")
+                    .and_then(|number| number.parse().ok())
+                    .map(Card::Clock)
+                    .ok_or(strum::ParseError::VariantNotFound)
+            })
+    }
+}
+
+/// Lets the card types appear in query strings by their bare names.
+macro_rules! form_field_from_str {
+    ($($card:ty),*) => {$(
+        #[rocket::async_trait]
+        impl<'v> FromFormField<'v> for $card {
+            fn from_value(field: ValueField<'v>) -> form::Result<'v, Self> {
+                field
+                    .value
+                    .parse()
+                    .map_err(|_| form::Error::validation("unknown card").into())
+            }
+        }
+    )*};
+}
+
+form_field_from_str!(Card, Suspect, Weapon, Room);
+
+#[derive(Debug, Clone, EnumIter, EnumString, PartialEq, Serialize, Deserialize)]
+enum Suspect {
+    Plum,
+    Green,
+    Mustard,
+    Peacock,
+    Scarlett,
+    Orchid,
+}
+
+#[derive(Debug, Clone, EnumIter, EnumString, PartialEq, Serialize, Deserialize)]
+enum Weapon {
+    Candlestick,
+    LeadPipe,
+    Dagger,
+    Rope,
+    Revolver,
+    Wrench,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, EnumIter, EnumString, PartialEq, Serialize, Deserialize)]
+enum Room {
+    Kitchen,
+    Hall,
+    Lounge,
+    Ballroom,
+    Conservatory,
+    DiningRoom,
+    Library,
+    BilliardRoom,
+    Study,
+}
+
+// struct Won(i8);
+
+/// Joins the lobby. The name stays in the path, where every other player
+/// route expects it, so names that wouldn't survive there are refused.
+#[post("/players/<name>?<color>")]
+fn create_player(
+    name: &str,
+    color: Option<&str>,
+    game_state: &State<SharedGame>,
+    dealer: &State<Box<dyn Dealer>>,
+    audit: &State<AuditLog>,
+) -> Result<(Status, (ContentType, String)), ApiError> {
+    let mut state = game_state.lock()?;
+
+    if state.phase != Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game already started")
+            .with_details(json!({ "phase": state.phase })));
+    }
+
+    if let Some(reason) = name_problem(name) {
+        return Err(ApiError::new(Status::UnprocessableEntity, "invalid name")
+            .with_details(json!({ "name": name, "reason": reason })));
+    }
+
+    if let Some(index) = state.player_index(name) {
+        return Err(
+            ApiError::new(Status::Conflict, "name already taken").with_details(json!({
+                "existing": state.players[index].name,
+                "key": name_key(name),
+            })),
+        );
+    }
+
+    // Custom decks may have fewer suspects than seats.
+    let character = state.free_character().ok_or_else(|| {
+        ApiError::new(Status::Conflict, "no characters remaining")
+            .with_details(json!({ "suspects": state.settings.deck.suspects }))
+    })?;
+
+    let mut player = Player::new(name, Some(character));
+    if let Some(color) = color {
+        player.color = Some(state.claim_color(color, None)?);
+    }
+
+    let body = to_string(&JoinResponse {
+        name: &player.name,
+        token: &player.token,
+    })
+    .unwrap();
+
+    if state.players.is_empty() {
+        state.host = player.name.clone();
+    }
+    state.record_event(GameEvent::Join {
+        player: player.name.clone(),
+    });
+    state.players.push(player);
+
+    if state.settings.auto_start_at == Some(state.players.len()) {
+        // The join itself stands even if the game can't start yet.
+        let settings = state.settings.clone();
+        if let Err(error) = state.start(settings, dealer.as_ref(), audit) {
+            audit.record("auto-start failed", to_string(&error).unwrap());
+        }
+    }
+
+    Ok((Status::Created, (ContentType::JSON, body)))
+}
+
+/// Leaves the game. Players only ever remove themselves; the host has
+/// `/game/kick` for anyone else in the lobby.
+#[delete("/players/<name>")]
+fn delete_player(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+    audit: &State<AuditLog>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    let index = own_player_index(&state, name, &token)?;
+    state.ensure_not_retained()?;
+
+    // Mid-game their hand and seat are still part of the game, so they
+    // resign instead of disappearing.
+    if state.phase == Phase::InProgress {
+        if !state.players[index].eliminated {
+            state.eliminate(index, EliminationReason::Resigned);
+        }
+        return Ok(Status::NoContent);
+    }
+
+    // Nobody is left to play or look back on it, so the game goes.
+    let removed = state.players.remove(index);
+    if state.players.is_empty() {
+        audit.record(
+            "game abandoned",
+            format!("{} left the {:?} game last", removed.name, state.phase),
+        );
+        state.reset();
+    } else if state.host == removed.name {
+        state.host = state.players[0].name.clone();
+    }
+
+    Ok(Status::NoContent)
+}
+
+#[patch("/players/<name>/color", data = "<request>")]
+fn set_color(
+    name: &str,
+    token: PlayerToken,
+    request: Json<ColorRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    if state.players[index].token != token.0 {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "players may only change their own color",
+        ));
+    }
+    state.ensure_not_retained()?;
+
+    state.players[index].color = Some(state.claim_color(&request.color, Some(index))?);
+
+    Ok(Status::NoContent)
+}
+
+/// Looks up `name`, refusing anyone but that player themselves.
+fn own_player_index(state: &GameState, name: &str, token: &PlayerToken) -> Result<usize, ApiError> {
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    if state.players[index].token != token.0 {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "players may only act for themselves",
+        ));
+    }
+
+    Ok(index)
+}
+
+#[get("/game/notebook/<name>")]
+fn get_notebook(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let player = &state.players[own_player_index(state, name, &token)?];
+
+    // The cards other players have shown this one, or the whole table.
+    let shown: Vec<&DisproofOutcome> = state
+        .history
+        .iter()
+        .enumerate()
+        .filter(|(index, record)| {
+            matches!(&record.outcome, DisproofOutcome::Disproved { shown_to, .. }
+                if *shown_to == player.name || state.shown_publicly(*index))
+        })
+        .map(|(_, record)| &record.outcome)
+        .collect();
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "cards": player.cards, "marks": player.notebook, "shown": shown }))
+            .unwrap(),
+    ))
+}
+
+#[get("/game/unseen-count/<name>")]
+fn get_unseen_count(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = own_player_index(state, name, &token)?;
+    let total = state.settings.deck.cards().len();
+    let unseen = total - state.seen_cards(index).len();
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "unseen": unseen, "total": total })).unwrap(),
+    ))
+}
+
+/// Whether the player can prove the whole solution from what they know, so
+/// an accusation is safe. Never looks at the envelope itself.
+#[get("/game/solved/<name>")]
+fn get_solved(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = own_player_index(state, name, &token)?;
+    let suspect = state.deduced(index, "suspect");
+    let weapon = state.deduced(index, "weapon");
+    let room = state.deduced(index, "room");
+    let solved = suspect.is_some() && weapon.is_some() && room.is_some();
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({
+            "solved": solved,
+            "suspect": suspect,
+            "weapon": weapon,
+            "room": room,
+        }))
+        .unwrap(),
+    ))
+}
+
+/// Whether the player can still win: they are in a game that is running
+/// and haven't been knocked out of it. Deliberately conservative, since a
+/// player with cards left to learn can always go on asking.
+#[get("/game/winnable/<name>")]
+fn get_winnable(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = own_player_index(state, name, &token)?;
+    let reason = if state.phase != Phase::InProgress {
+        Some("game not in progress")
+    } else if state.players[index].eliminated {
+        Some("eliminated")
+    } else {
+        None
+    };
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "winnable": reason.is_none(), "reason": reason })).unwrap(),
+    ))
+}
+
+/// The player's notebook as a row per card, for keeping outside the game.
+#[get("/game/notebook/<name>/export?<format>")]
+fn export_notebook(
+    name: &str,
+    format: Option<NotebookFormat>,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = own_player_index(state, name, &token)?;
+    let player = &state.players[index];
+    let seen = state.seen_cards(index);
+
+    let rows: Vec<NotebookRow> = state
+        .settings
+        .deck
+        .cards()
+        .into_iter()
+        .map(|card| {
+            let mark = player.notebook.iter().find(|entry| entry.card == card);
+            NotebookRow {
+                category: card.category(),
+                mark: mark.map(|entry| match entry.mark {
+                    Mark::Have => "have",
+                    Mark::HeldBy(_) => "held_by",
+                    Mark::Maybe => "maybe",
+                }),
+                held_by: mark.and_then(|entry| match &entry.mark {
+                    Mark::HeldBy(holder) => Some(holder.as_str()),
+                    _ => None,
+                }),
+                seen: seen.contains(&&card),
+                card: card.name(),
+            }
+        })
+        .collect();
+
+    Ok(match format.unwrap_or_default() {
+        NotebookFormat::Json => (ContentType::JSON, to_string(&rows).unwrap()),
+        NotebookFormat::Csv => {
+            let mut csv = String::from(NotebookRow::CSV_HEADER);
+            for row in &rows {
+                csv.push('\n');
+                csv.push_str(&row.to_csv());
+            }
+            csv.push('\n');
+            (ContentType::CSV, csv)
+        }
+    })
+}
+
+#[post("/game/notebook/<name>/mark", data = "<request>")]
+fn mark_notebook(
+    name: &str,
+    token: PlayerToken,
+    request: Json<MarkRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    let index = own_player_index(&state, name, &token)?;
+    state.ensure_not_retained()?;
+    let MarkRequest { card, mark } = request.into_inner();
+
+    let notebook = &mut state.players[index].notebook;
+    notebook.retain(|entry| entry.card != card);
+    if let Some(mark) = mark {
+        notebook.push(NotebookMark { card, mark });
+    }
+
+    Ok(Status::NoContent)
+}
+
+#[get("/players")]
+fn get_players(
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+    cache: &State<ViewCache>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let (state, version) = game_state.snapshot()?;
+
+    let viewer = state.viewer_name(token.as_ref());
+    let render = || encoding.encode(&state.view_for(viewer, admin.is_some()).players);
+
+    // Only the anonymous view is the same for everyone.
+    Ok(match (viewer, &admin) {
+        (None, None) => cache.get_or_render("players", encoding, version, render),
+        _ => render(),
+    })
+}
+
+#[get("/players/<name>?<sort>")]
+fn get_player(
+    name: &str,
+    sort: Option<HandSort>,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let (state, _) = &game_state.snapshot()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    let mut view = state.view_for(state.viewer_name(token.as_ref()), admin.is_some());
+    let mut player = view.players.swap_remove(index);
+
+    let sorted = player
+        .cards
+        .filter(|_| sort == Some(HandSort::Category))
+        .map(|cards| {
+            let mut cards = cards.to_vec();
+            cards.sort_by_key(Card::catalogue_key);
+            cards
+        });
+    if let Some(sorted) = &sorted {
+        player.cards = Some(sorted);
+    }
+
+    Ok((ContentType::JSON, to_string(&player).unwrap()))
+}
+
+/// Settles the settings before the game starts, so that joining players
+/// take characters from the right deck.
+#[put("/game/settings", data = "<settings>")]
+fn update_settings(
+    token: PlayerToken,
+    settings: Json<Value>,
+    game_state: &State<SharedGame>,
+    editions: &State<Editions>,
+) -> Result<(ContentType, String), ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(&token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can change the settings",
+        ));
+    }
+    if state.phase != Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game already started"));
+    }
+
+    let settings = GameSettings::from_body(Some(settings.0), editions)?;
+    settings.validate()?;
+
+    state.settings = settings;
+
+    Ok((ContentType::JSON, to_string(&state.settings).unwrap()))
+}
+
+#[post("/game", data = "<settings>")]
+fn create_game(
+    token: PlayerToken,
+    admin: Option<AdminToken>,
+    settings: Option<Json<Value>>,
+    game_state: &State<SharedGame>,
+    editions: &State<Editions>,
+    dealer: &State<Box<dyn Dealer>>,
+    audit: &State<AuditLog>,
+) -> Result<(Status, (ContentType, String)), ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(&token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can start the game",
+        ));
+    }
+
+    // Without a body the game starts with whatever the lobby settled on.
+    let (mut settings, hands) = match settings {
+        Some(Json(mut body)) => {
+            let hands = body.as_object_mut().and_then(|body| body.remove("hands"));
+            (GameSettings::from_body(Some(body), editions)?, hands)
+        }
+        None => (state.settings.clone(), None),
+    };
+    settings.validate()?;
+
+    if let Some(hands) = hands {
+        if admin.is_none() {
+            return Err(ApiError::new(
+                Status::Forbidden,
+                "only admins may fix the hands",
+            ));
+        }
+        settings.fixed_hands = Some(serde_json::from_value(hands).map_err(|error| {
+            ApiError::new(Status::UnprocessableEntity, "invalid hands")
+                .with_details(json!({ "error": error.to_string() }))
+        })?);
+        settings
+            .fixed_solution(&state.players)
+            .map_err(GameSettings::hands_error)?;
+    }
+
+    state.start(settings, dealer.as_ref(), audit)?;
+
+    let host = state.host.clone();
+    let created = CreatedGame {
+        state: state.view_for(Some(&host), false),
+        fairness: DealFairness::of(&state.players),
+        solution_seed: state.settings.solution_seed.filter(|_| admin.is_some()),
+        deal_seed: state.settings.deal_seed.filter(|_| admin.is_some()),
+    };
+
+    Ok((
+        Status::Created,
+        (ContentType::JSON, to_string(&created).unwrap()),
+    ))
+}
+
+#[post("/game/rematch")]
+fn rematch(
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+    dealer: &State<Box<dyn Dealer>>,
+    audit: &State<AuditLog>,
+) -> Result<(Status, (ContentType, String)), ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(&token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can start a rematch",
+        ));
+    }
+    state.ensure_not_retained()?;
+
+    state.rematch(dealer.as_ref(), audit)?;
+
+    let host = state.host.clone();
+    let created = CreatedGame {
+        state: state.view_for(Some(&host), false),
+        fairness: DealFairness::of(&state.players),
+        solution_seed: None,
+        deal_seed: None,
+    };
+
+    Ok((
+        Status::Created,
+        (ContentType::JSON, to_string(&created).unwrap()),
+    ))
+}
+
+/// Checks settings against the lobby as it stands without starting
+/// anything: the errors `POST /game` would refuse them with, and warnings.
+#[post("/game/validate-config", data = "<settings>")]
+fn validate_config(
+    settings: Option<Json<Value>>,
+    game_state: &State<SharedGame>,
+    editions: &State<Editions>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = game_state.lock()?;
+
+    // Without a body, check the lobby settings `POST /game` would use.
+    let settings = match settings {
+        Some(body) => GameSettings::from_body(Some(body.0), editions),
+        None => Ok(state.settings.clone()),
+    };
+
+    let body = match settings {
+        Ok(settings) => {
+            let mut errors = settings.errors();
+            let problems = state.start_problems(&settings);
+            if !problems.is_empty() {
+                errors.push(
+                    ApiError::new(Status::Conflict, "game cannot start")
+                        .with_details(json!({ "phase": state.phase, "problems": problems })),
+                );
+            }
+
+            json!({
+                "valid": errors.is_empty(),
+                "errors": errors,
+                "warnings": settings.warnings(state.players.len()),
+            })
+        }
+        Err(error) => json!({ "valid": false, "errors": [error], "warnings": [] }),
+    };
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+/// Deals a throwaway game from the lobby, or from the settings given, to
+/// check that a custom deck comes out balanced. Nothing is kept.
+#[post("/game/preview-deal", data = "<settings>")]
+fn preview_deal(
+    _admin: AdminToken,
+    settings: Option<Json<Value>>,
+    game_state: &State<SharedGame>,
+    editions: &State<Editions>,
+    dealer: &State<Box<dyn Dealer>>,
+    audit: &State<AuditLog>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = game_state.lock()?;
+
+    if state.phase != Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game already started"));
+    }
+    if state.players.is_empty() {
+        return Err(ApiError::new(Status::Conflict, "nobody to deal to"));
+    }
+
+    let mut preview = state.clone();
+    if let Some(body) = settings {
+        preview.settings = GameSettings::from_body(Some(body.0), editions)?;
+    }
+    preview.settings.validate()?;
+    preview.settings.pick_seeds();
+    preview.deal(dealer.as_ref(), audit)?;
+
+    let body = json!({
+        "hand_sizes": preview.players.iter().map(|p| p.cards.len()).collect::<Vec<_>>(),
+        "fairness": DealFairness::of(&preview.players),
+        "solution": preview.solution,
+        "solution_seed": preview.settings.solution_seed,
+        "deal_seed": preview.settings.deal_seed,
+    });
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+/// What a standard deck would deal out to `players` seats from `seed`,
+/// which seeds both the solution and the shuffle. Works on a scratch game,
+/// so nothing real is touched, not even the audit log. `labeled` adds every
+/// card of the deck with where it went.
+#[get("/simulate/deal?<players>&<seed>&<labeled>")]
+fn simulate_deal(
+    players: usize,
+    seed: Option<u64>,
+    labeled: bool,
+    dealer: &State<Box<dyn Dealer>>,
+) -> Result<(ContentType, String), ApiError> {
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+        return Err(
+            ApiError::new(Status::UnprocessableEntity, "invalid player count")
+                .with_details(json!({ "min": MIN_PLAYERS, "max": MAX_PLAYERS, "given": players })),
+        );
+    }
+
+    let mut scratch = GameState::new();
+    scratch.players = (1..=players)
+        .map(|seat| Player::new(&format!("player {seat}"), None))
+        .collect();
+    scratch.settings.solution_seed = seed;
+    scratch.settings.deal_seed = seed;
+    scratch.settings.pick_seeds();
+    scratch.deal(dealer.as_ref(), &AuditLog::default())?;
+
+    let mut body = json!({
+        "solution": scratch.solution,
+        "hands": scratch.players.iter().map(|p| &p.cards).collect::<Vec<_>>(),
+        "solution_seed": scratch.settings.solution_seed,
+        "deal_seed": scratch.settings.deal_seed,
+    });
+
+    if labeled {
+        let solution = scratch.solution.as_ref().unwrap().as_cards();
+        let labels: Vec<Value> = scratch
+            .settings
+            .deck
+            .cards()
+            .into_iter()
+            .map(|card| {
+                let holder = match scratch.players.iter().find(|p| p.cards.contains(&card)) {
+                    Some(player) => player.name.as_str(),
+                    None if solution.contains(&card) => "solution",
+                    None => "undealt",
+                };
+                json!({ "card": card, "holder": holder })
+            })
+            .collect();
+        body["cards"] = labels.into();
+    }
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+/// Opponents the daily puzzle is played against.
+const DAILY_OPPONENTS: usize = 3;
+
+/// Days since the Unix epoch of a `YYYY-MM-DD` date, or of today (UTC)
+/// when none is given.
+fn daily_day(date: Option<&str>) -> Result<u64, ApiError> {
+    let Some(date) = date else {
+        return Ok(now_secs() / 86_400);
+    };
+
+    let invalid = || {
+        ApiError::new(Status::UnprocessableEntity, "invalid date")
+            .with_details(json!({ "date": date, "expected": "YYYY-MM-DD" }))
+    };
+    let mut parts = date.splitn(3, '-').map(str::parse::<u64>);
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    if !(1970..=9999).contains(&year) || !(1..=12).contains(&month) || day == 0 {
+        return Err(invalid());
+    }
+
+    // Shift the year to start in March, so the leap day falls last.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468);
+
+    // Round-tripping throws out days the month doesn't have.
+    match days {
+        Some(days) if daily_date(days) == date => Ok(days),
+        _ => Err(invalid()),
+    }
+}
+
+/// The `YYYY-MM-DD` date `days` after the Unix epoch.
+fn daily_date(days: u64) -> String {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The day's puzzle, dealt from a seed of the day itself: the same for
+/// everyone, and never stored.
+fn daily_puzzle(days: u64, dealer: &dyn Dealer) -> Result<GameState, ApiError> {
+    let mut puzzle = GameState::new();
+    puzzle.players = std::iter::once(Player::new("you", None))
+        .chain((1..=DAILY_OPPONENTS).map(|seat| Player::new(&format!("opponent {seat}"), None)))
+        .collect();
+    puzzle.settings.solution_seed = Some(days);
+    puzzle.settings.deal_seed = Some(days);
+    puzzle.deal(dealer, &AuditLog::default())?;
+
+    Ok(puzzle)
+}
+
+/// Today's puzzle, or another day's: the player's hand and how many cards
+/// each opponent holds.
+#[get("/daily?<date>")]
+fn get_daily(
+    date: Option<&str>,
+    dealer: &State<Box<dyn Dealer>>,
+) -> Result<(ContentType, String), ApiError> {
+    let days = daily_day(date)?;
+    let puzzle = daily_puzzle(days, dealer.as_ref())?;
+
+    let opponents: Vec<Value> = puzzle.players[1..]
+        .iter()
+        .map(|opponent| json!({ "name": opponent.name, "hand_size": opponent.cards.len() }))
+        .collect();
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({
+            "date": daily_date(days),
+            "hand": puzzle.players[0].cards,
+            "opponents": opponents,
+        }))
+        .unwrap(),
+    ))
+}
+
+/// Puts a suggestion to the day's opponents, asked in seat order: the first
+/// who can disprove it shows one card.
+#[get("/daily/suggest?<date>&<suggestion..>")]
+fn daily_suggest(
+    date: Option<&str>,
+    suggestion: Suggestion,
+    dealer: &State<Box<dyn Dealer>>,
+) -> Result<(ContentType, String), ApiError> {
+    let days = daily_day(date)?;
+    let puzzle = daily_puzzle(days, dealer.as_ref())?;
+
+    let cards = suggestion.as_cards();
+    let disproof = puzzle.players[1..].iter().find_map(|opponent| {
+        let card = opponent.cards.iter().find(|card| cards.contains(card))?;
+        Some(json!({ "by": opponent.name, "card": card }))
+    });
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "date": daily_date(days), "disproof": disproof })).unwrap(),
+    ))
+}
+
+#[get("/daily/accuse?<date>&<accusation..>")]
+fn daily_accuse(
+    date: Option<&str>,
+    accusation: Suggestion,
+    dealer: &State<Box<dyn Dealer>>,
+) -> Result<(ContentType, String), ApiError> {
+    let days = daily_day(date)?;
+    let puzzle = daily_puzzle(days, dealer.as_ref())?;
+
+    let correct = puzzle.solution.as_ref().unwrap().as_cards() == accusation.as_cards();
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "date": daily_date(days), "correct": correct })).unwrap(),
+    ))
+}
+
+#[get("/game")]
+fn get_game(
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+    cache: &State<ViewCache>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let (state, version) = game_state.snapshot()?;
+
+    let viewer = state.viewer_name(token.as_ref());
+    let render = || encoding.encode(&state.view_for(viewer, admin.is_some()));
+
+    // Only the anonymous view is the same for everyone.
+    Ok(match (viewer, &admin) {
+        (None, None) => cache.get_or_render("game", encoding, version, render),
+        _ => render(),
+    })
+}
+
+#[get("/game/practice/hypothetical?<suggestion..>")]
+fn practice_hypothetical(
+    token: PlayerToken,
+    suggestion: Suggestion,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    if !state.settings.practice {
+        return Err(ApiError::new(Status::Conflict, "not a practice game"));
+    }
+    if state.phase != Phase::InProgress {
+        return Err(ApiError::new(Status::Conflict, "game not in progress"));
+    }
+
+    let viewer = state
+        .token_index(&token)
+        .ok_or_else(|| ApiError::new(Status::Unauthorized, "unknown player token"))?;
+
+    let holders: Vec<Value> = suggestion
+        .as_cards()
+        .iter()
+        .map(|card| json!({ "card": card, "possible_holders": state.possible_holders(viewer, card) }))
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&holders).unwrap()))
+}
+
+#[get("/game/probability/<name>?<card>")]
+fn get_probability(
+    name: &str,
+    card: Card,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    if state.phase == Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game not started"));
+    }
+
+    let viewer = state
+        .token_index(&token)
+        .ok_or_else(|| ApiError::new(Status::Unauthorized, "unknown player token"))?;
+    let holder = state.player_index(name).ok_or(Status::NotFound)?;
+
+    let body = json!({
+        "name": state.players[holder].name,
+        "card": card,
+        "probability": state.holding_probability(viewer, holder, &card),
+    });
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+/// The board of the game being set up or played.
+#[get("/board")]
+fn get_board(game_state: &State<SharedGame>) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+    let layout = Layout::with_passages(&state.settings.secret_passages);
+
+    Ok((ContentType::JSON, to_string(&layout).unwrap()))
+}
+
+/// The fewest steps between two rooms, through the corridors or the
+/// secret passages of the game being set up or played.
+#[get("/board/distance?<from>&<to>")]
+fn get_distance(
+    from: &str,
+    to: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let parse = |room: &str| {
+        room.parse::<Room>().map_err(|_| {
+            ApiError::new(Status::NotFound, "unknown room").with_details(json!({ "room": room }))
+        })
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+
+    let state = &game_state.lock()?;
+    let settings = &state.settings;
+    let steps = board::distance(
+        &settings.secret_passages,
+        settings.passage_steps,
+        &from,
+        &to,
+    );
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "from": from, "to": to, "steps": steps })).unwrap(),
+    ))
+}
+
+#[get("/editions")]
+fn get_editions(editions: &State<Editions>) -> (ContentType, String) {
+    (ContentType::JSON, to_string(&*editions.registry()).unwrap())
+}
+
+/// Registers an edition for `POST /game` and the lobby settings to name.
+/// Its deck and board are checked as a game's would be.
+#[post("/editions", data = "<request>")]
+fn register_edition(
+    _admin: AdminToken,
+    request: Json<EditionRequest>,
+    editions: &State<Editions>,
+) -> Result<(Status, (ContentType, String)), ApiError> {
+    let EditionRequest { name, edition } = request.into_inner();
+
+    if name.trim().is_empty() {
+        return Err(ApiError::new(
+            Status::UnprocessableEntity,
+            "edition needs a name",
+        ));
+    }
+    GameSettings {
+        deck: edition.deck.clone(),
+        secret_passages: edition.secret_passages.clone(),
+        ..GameSettings::default()
+    }
+    .validate()?;
+
+    let mut registry = editions.registry();
+    if registry.contains_key(&name) {
+        return Err(
+            ApiError::new(Status::Conflict, "edition already registered")
+                .with_details(json!({ "edition": name })),
+        );
+    }
+    let body = to_string(&edition).unwrap();
+    registry.insert(name, edition);
+
+    Ok((Status::Created, (ContentType::JSON, body)))
+}
+
+/// Takes an edition off the registry. Games already set up from it keep
+/// their own copy of its deck and board.
+#[delete("/editions/<name>")]
+fn delete_edition(
+    name: &str,
+    _admin: AdminToken,
+    editions: &State<Editions>,
+) -> Result<Status, ApiError> {
+    match editions.registry().remove(name) {
+        Some(_) => Ok(Status::NoContent),
+        None => Err(ApiError::new(Status::NotFound, "unknown edition")
+            .with_details(json!({ "edition": name }))),
+    }
+}
+
+#[get("/stats")]
+fn get_stats(game_state: &State<SharedGame>) -> (ContentType, String) {
+    let archive = game_state
+        .archive
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    (ContentType::JSON, to_string(&Stats::of(&archive)).unwrap())
+}
+
+/// Every preset, with the settings it expands to. Those that can't be
+/// played yet are listed too, marked unavailable.
+#[get("/rules/presets")]
+fn get_presets() -> (ContentType, String) {
+    let available = PRESETS.iter().map(
+        |&name| json!({ "name": name, "available": true, "settings": GameSettings::preset(name) }),
+    );
+    let unavailable = UNAVAILABLE_PRESETS
+        .iter()
+        .map(|&(name, reason)| json!({ "name": name, "available": false, "reason": reason }));
+    let presets: Vec<Value> = available.chain(unavailable).collect();
+
+    (ContentType::JSON, to_string(&presets).unwrap())
+}
+
+#[get("/game/summary")]
+fn get_summary(game_state: &State<SharedGame>) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let summary = GameSummary {
+        id: &state.id,
+        created_at: state.opened_at,
+        phase: state.phase,
+        host: &state.host,
+        players: state.players.len(),
+        started_at: (state.phase != Phase::Lobby).then_some(state.created_at),
+        joinable: state.phase == Phase::Lobby && state.free_character().is_some(),
+    };
+
+    Ok((ContentType::JSON, to_string(&summary).unwrap()))
+}
+
+#[get("/game/rules")]
+fn get_rules(game_state: &State<SharedGame>) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    Ok((
+        ContentType::JSON,
+        to_string(&state.settings.rules()).unwrap(),
+    ))
+}
+
+/// The settings the game runs on, enough to set up the same game again.
+/// `solution_seed` and `deal_seed` would give the solution away, so only
+/// admins see them before the game is over.
+#[get("/game/config")]
+fn get_config(
+    admin: Option<AdminToken>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let mut config = to_value(&state.settings).unwrap();
+    if admin.is_none() && state.phase != Phase::Finished {
+        config["solution_seed"] = Value::Null;
+        config["deal_seed"] = Value::Null;
+    }
+
+    Ok((ContentType::JSON, to_string(&config).unwrap()))
+}
+
+/// Ends the game for everyone. The host or an admin may delete it; with
+/// `?reveal=true` an admin also gets the solution back, where the server
+/// allows it.
+#[delete("/game?<reveal>")]
+fn delete_game(
+    reveal: bool,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    config: &State<AdminConfig>,
+    game_state: &State<SharedGame>,
+) -> Result<Either<Status, (ContentType, String)>, ApiError> {
+    let mut state = game_state.lock()?;
+
+    if admin.is_none() && !token.is_some_and(|token| state.is_host(&token)) {
+        return Err(Status::Forbidden.into());
+    }
+    if reveal && admin.is_none() {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only admins may reveal the solution",
+        ));
+    }
+    if reveal && !config.reveal_on_delete {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "reveal_on_delete is not enabled",
+        ));
+    }
+
+    if admin.is_none() {
+        state.ensure_not_retained()?;
+    }
+
+    let Some(solution) = state.solution.take() else {
+        return Err(Status::BadRequest.into());
+    };
+
+    state.reset();
+
+    if reveal {
+        let body = to_string(&json!({ "solution": solution })).unwrap();
+        return Ok(Either::Right((ContentType::JSON, body)));
+    }
+
+    Ok(Either::Left(Status::NoContent))
+}
+
+#[post("/game/host", data = "<transfer>")]
+fn transfer_host(
+    token: PlayerToken,
+    transfer: Json<HostTransfer>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(&token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can hand over the host role",
+        ));
+    }
+    state.ensure_not_retained()?;
+
+    match state.player_index(&transfer.name) {
+        Some(index) => state.host = state.players[index].name.clone(),
+        None => {
+            return Err(ApiError::new(Status::NotFound, "no such player")
+                .with_details(json!({ "name": transfer.name })));
+        }
+    }
+
+    Ok(Status::Ok)
+}
+
+#[post("/game/kick/<name>")]
+fn kick_player(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(&token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can kick players",
+        ));
+    }
+    state.ensure_not_retained()?;
+
+    if state.phase != Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game already started")
+            .with_details(json!({ "phase": state.phase })));
+    }
+
+    match state.player_index(name) {
+        Some(index) if state.players[index].name == state.host => {
+            Err(ApiError::new(Status::BadRequest, "cannot kick the host"))
+        }
+        Some(index) => {
+            state.players.remove(index);
+            Ok(Status::NoContent)
+        }
+        None => {
+            Err(ApiError::new(Status::NotFound, "no such player")
+                .with_details(json!({ "name": name })))
+        }
+    }
+}
+
+#[post("/game/pause")]
+fn pause_game(token: PlayerToken, game_state: &State<SharedGame>) -> Result<Status, ApiError> {
+    set_paused(true, &token, game_state)
+}
+
+#[post("/game/resume")]
+fn resume_game(token: PlayerToken, game_state: &State<SharedGame>) -> Result<Status, ApiError> {
+    set_paused(false, &token, game_state)
+}
+
+fn set_paused(
+    paused: bool,
+    token: &PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    if !state.is_host(token) {
+        return Err(ApiError::new(
+            Status::Forbidden,
+            "only the host can pause or resume the game",
+        ));
+    }
+
+    if state.phase != Phase::InProgress {
+        return Err(ApiError::new(Status::Conflict, "game not in progress")
+            .with_details(json!({ "phase": state.phase })));
+    }
+    if state.paused == paused {
+        let error = if paused {
+            "game already paused"
+        } else {
+            "game not paused"
+        };
+        return Err(ApiError::new(Status::Conflict, error)
+            .with_details(json!({ "phase": state.phase, "paused": state.paused })));
+    }
+
+    // The turn timer stands still while the game is paused.
+    let now = now_secs();
+    if paused {
+        state.paused_at = now;
+    } else {
+        let paused_for = now.saturating_sub(state.paused_at);
+        state.turn_started_at += paused_for;
+        state.disproof_started_at += paused_for;
+    }
+
+    state.paused = paused;
+
+    Ok(Status::NoContent)
+}
+
+#[post("/suggest", data = "<suggestion>")]
+fn suggest(
+    player: Result<ActivePlayer, ApiError>,
+    suggestion: Json<Suggestion>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let suggester = player.index(&state)?;
+    let duplicate = state.suggest(suggester, suggestion.into_inner())?;
+    state.action_seq += 1;
+
+    let result = state.last_suggestion_result(duplicate);
+
+    Ok((ContentType::JSON, to_string(&result).unwrap()))
+}
+
+#[post("/game/reveal", data = "<request>")]
+fn reveal(
+    token: PlayerToken,
+    request: Json<RevealRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    state.ensure_not_paused()?;
+    state.ensure_not_retained()?;
+
+    let responder = state
+        .token_index(&token)
+        .ok_or_else(|| ApiError::new(Status::Unauthorized, "unknown player token"))?;
+
+    let name = &state.players[responder].name;
+    let Some(pending) = state
+        .pending_disproof()
+        .filter(|&index| state.history[index].outcome.disprover() == Some(name.as_str()))
+    else {
+        return Err(ApiError::new(Status::Conflict, "no disproof awaiting you"));
+    };
+
+    let card = request.into_inner().card;
+    if !state.players[responder].cards.contains(&card) {
+        return Err(
+            ApiError::new(Status::UnprocessableEntity, "card not in your hand")
+                .with_details(json!({ "card": card })),
+        );
+    }
+
+    let suggested = state.history[pending].suggestion.as_cards();
+    if !suggested.contains(&card) {
+        return Err(ApiError::new(
+            Status::UnprocessableEntity,
+            "card does not disprove the suggestion",
+        )
+        .with_details(json!({ "card": card, "suggested": suggested })));
+    }
+
+    let by = name.clone();
+    let shown_to = state.history[pending].suggested_by.clone();
+    state.history[pending].outcome = DisproofOutcome::Disproved { by, shown_to, card };
+    state.record_event(GameEvent::Reveal {
+        suggestion: pending,
+    });
+
+    Ok(Status::NoContent)
+}
+
+#[post("/game/move", data = "<request>")]
+fn move_player(
+    player: Result<ActivePlayer, ApiError>,
+    request: Json<MoveRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let mover = player.index(&state)?;
+    state.move_token(mover, request.into_inner())?;
+    state.action_seq += 1;
+
+    Ok(Status::NoContent)
+}
+
+/// Plays a turn in one request. If any step is refused the game is put
+/// back as it was, and the error names the step.
+#[post("/game/turn", data = "<request>")]
+fn take_turn(
+    player: Result<ActivePlayer, ApiError>,
+    request: Json<TurnRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let index = player.index(&state)?;
+    let TurnRequest {
+        roll,
+        movement,
+        suggest,
+        end_turn,
+    } = request.into_inner();
+
+    let before = state.clone();
+    let fail = |state: &mut GameState, step: &str, error: ApiError| {
+        *state = before.clone();
+        let cause = json!({ "error": error.error, "details": error.details });
+        ApiError::new(error.status, "turn refused")
+            .with_details(json!({ "step": step, "cause": cause }))
+    };
+
+    let mut changes = Map::new();
+    if roll {
+        match state.roll() {
+            Ok(roll) => changes.insert("roll".into(), roll.into()),
+            Err(error) => return Err(fail(&mut state, "roll", error)),
+        };
+    }
+    if let Some(movement) = movement {
+        let room = movement.room.clone();
+        if let Err(error) = state.move_token(index, movement) {
+            return Err(fail(&mut state, "move", error));
+        }
+        changes.insert("moved_to".into(), json!(room));
+    }
+    if let Some(suggestion) = suggest {
+        let duplicate = match state.suggest(index, suggestion) {
+            Ok(duplicate) => duplicate,
+            Err(error) => return Err(fail(&mut state, "suggest", error)),
+        };
+        let result = state.last_suggestion_result(duplicate);
+        changes.insert("suggestion".into(), json!(result));
+    }
+    if end_turn {
+        if let Err(error) = state
+            .ensure_no_pending_disproof()
+            .and_then(|()| state.ensure_suggestion_decided(index))
+        {
+            return Err(fail(&mut state, "end_turn", error));
+        }
+        state.advance_turn();
+        changes.insert("turn_number".into(), state.turn_number.into());
+    }
+    state.action_seq += 1;
+    changes.insert("action_seq".into(), state.action_seq.into());
+
+    Ok((ContentType::JSON, to_string(&changes).unwrap()))
+}
+
+#[post("/game/roll")]
+fn roll(
+    player: Result<ActivePlayer, ApiError>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    player.index(&state)?;
+    let roll = state.roll()?;
+    state.action_seq += 1;
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "roll": roll })).unwrap(),
+    ))
+}
+
+#[get("/game/remaining-steps/<name>")]
+fn get_remaining_steps(
+    name: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "remaining_steps": state.remaining_steps(index) })).unwrap(),
+    ))
+}
+
+#[post("/game/accuse", data = "<accusation>")]
+fn accuse(
+    player: Result<ActivePlayer, ApiError>,
+    accusation: Json<Suggestion>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let accuser = player.index(&state)?;
+
+    state.ensure_no_pending_disproof()?;
+    state.ensure_in_deck(&accusation.as_cards())?;
+
+    let Some(solution) = state.solution.clone() else {
+        return Err(ApiError::new(Status::Conflict, "no solution"));
+    };
+
+    let correct = accusation.as_cards() == solution.as_cards();
+    let name = state.players[accuser].name.clone();
+
+    state.accusations.push(AccusationRecord {
+        player: name.clone(),
+        accusation: accusation.0,
+        correct,
+    });
+    state.record_event(GameEvent::Accuse {
+        player: name.clone(),
+        correct,
+    });
+
+    if correct {
+        state.winner = Some(name);
+        state.phase = Phase::Finished;
+    } else {
+        state.eliminate(accuser, EliminationReason::WrongAccusation);
+    }
+    state.action_seq += 1;
+
+    let result = AccusationResult {
+        correct,
+        solution: &solution,
+    };
+
+    Ok((ContentType::JSON, to_string(&result).unwrap()))
+}
+
+/// Plays a keeper card. Only the announcement is modelled so far; the
+/// card's effect is left to the players.
+#[post("/game/play-intrigue", data = "<request>")]
+fn play_intrigue(
+    player: Result<ActivePlayer, ApiError>,
+    request: Json<IntrigueRequest>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let index = player.index(&state)?;
+    let card = request.into_inner().card;
+
+    let hand = &mut state.players[index].intrigue_cards;
+    let position = hand.iter().position(|held| *held == card).ok_or_else(|| {
+        ApiError::new(Status::UnprocessableEntity, "intrigue card not held")
+            .with_details(json!({ "card": card }))
+    })?;
+    hand.remove(position);
+
+    let play = IntriguePlay {
+        player: state.players[index].name.clone(),
+        turn: state.turn_number,
+        card,
+    };
+    state.intrigue_played.push(play);
+    state.action_seq += 1;
+
+    Ok(Status::NoContent)
+}
+
+#[post("/game/resign/<name>")]
+fn resign(
+    name: &str,
+    token: PlayerToken,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+
+    state.ensure_not_paused()?;
+
+    if state.phase != Phase::InProgress {
+        return Err(ApiError::new(Status::Conflict, "game not in progress"));
+    }
+
+    let index = own_player_index(&state, name, &token)?;
+    if state.players[index].eliminated {
+        return Err(ApiError::new(Status::Conflict, "already out of the game"));
+    }
+
+    state.eliminate(index, EliminationReason::Resigned);
+
+    Ok(Status::NoContent)
+}
+
+/// Who won a finished game and how: by a correct accusation, by being the
+/// last player left, or nobody when it ran out of time.
+#[get("/game/winner")]
+fn get_winner(game_state: &State<SharedGame>) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    if state.phase != Phase::Finished {
+        return Err(ApiError::new(Status::NotFound, "game not finished")
+            .with_details(json!({ "phase": state.phase })));
+    }
+
+    let accusation = state
+        .accusations
+        .iter()
+        .find(|record| record.correct)
+        .map(|record| &record.accusation);
+    let won_by = match (&state.winner, accusation) {
+        (Some(_), Some(_)) => Some("accusation"),
+        (Some(_), None) => Some("last_player_standing"),
+        (None, _) => None,
+    };
+
+    let body = json!({
+        "winner": state.winner,
+        "won_by": won_by,
+        "accusation": accusation,
+        "timed_out": state.timed_out,
+    });
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+#[get("/game/eliminated")]
+fn get_eliminated(
+    admin: Option<AdminToken>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let announced = state.settings.announce_wrong_accusation || admin.is_some();
+
+    let eliminated: Vec<EliminatedPlayer> = state
+        .players
+        .iter()
+        .filter(|player| player.eliminated)
+        .map(|player| EliminatedPlayer {
+            name: &player.name,
+            reason: player.eliminated_reason.as_ref(),
+            accusation: state
+                .accusations
+                .iter()
+                .find(|record| !record.correct && record.player == player.name)
+                .map(|record| &record.accusation)
+                .filter(|_| announced),
+        })
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&eliminated).unwrap()))
+}
+
+#[post("/game/end-turn")]
+fn end_turn(
+    player: Result<ActivePlayer, ApiError>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let index = player.index(&state)?;
+    state.ensure_no_pending_disproof()?;
+    state.ensure_suggestion_decided(index)?;
+
+    state.advance_turn();
+
+    Ok(Status::NoContent)
+}
+
+/// Records that the active player is passing up their suggestion this turn,
+/// in the history and the events.
+#[post("/game/no-suggestion")]
+fn no_suggestion(
+    player: Result<ActivePlayer, ApiError>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let player = player?;
+    let mut state = game_state.lock()?;
+
+    let index = player.index(&state)?;
+    if state.turn.suggested || state.turn.declined_suggestion {
+        return Err(ApiError::new(
+            Status::Conflict,
+            "already decided on a suggestion this turn",
+        ));
+    }
+
+    state.turn.declined_suggestion = true;
+    let player = state.players[index].name.clone();
+    let turn = state.turn_number;
+    let after = state.history.len();
+    state.declined_suggestions.push(DeclinedSuggestion {
+        player: player.clone(),
+        turn,
+        after,
+    });
+    state.record_event(GameEvent::NoSuggestion { player, turn });
+    state.action_seq += 1;
+
+    Ok(Status::NoContent)
+}
+
+#[get("/game/available-actions/<name>")]
+fn get_available_actions(
+    name: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({
+            "actions": state.available_actions(index),
+            "action_seq": state.action_seq,
+        }))
+        .unwrap(),
+    ))
+}
+
+#[get("/game/moves/<name>")]
+fn get_moves(
+    name: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    let player = &state.players[index].name;
+
+    let moves: Vec<&MoveRecord> = state
+        .move_history
+        .iter()
+        .filter(|record| &record.player == player)
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&moves).unwrap()))
+}
+
+/// Each room the player has moved into this game, once, in the order first
+/// entered.
+#[get("/game/visited-rooms/<name>")]
+fn get_visited_rooms(
+    name: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    let player = &state.players[index].name;
+
+    let mut rooms: Vec<&Room> = Vec::new();
+    for record in state
+        .move_history
+        .iter()
+        .filter(|record| &record.player == player)
+    {
+        if !rooms.contains(&&record.room) {
+            rooms.push(&record.room);
+        }
+    }
+
+    Ok((ContentType::JSON, to_string(&rooms).unwrap()))
+}
+
+/// Debugging oracle for integration tests; never exposed to players.
+#[get("/game/is-solution?<card>")]
+fn is_solution(
+    card: Card,
+    _admin: AdminToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = game_state.lock()?;
+
+    let solution = state
+        .solution
+        .as_ref()
+        .ok_or_else(|| ApiError::new(Status::Conflict, "game not started"))?;
+
+    let body = json!({
+        "card": card,
+        "in_solution": solution.as_cards().contains(&card),
+    });
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+/// The deck's cards left out of play: neither dealt nor in the envelope,
+/// which are the ones set aside. Always empty for the standard deck.
+#[get("/game/unused-cards")]
+fn get_unused_cards(
+    _admin: AdminToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = game_state.lock()?;
+
+    let solution = state
+        .solution
+        .as_ref()
+        .ok_or_else(|| ApiError::new(Status::Conflict, "game not started"))?;
+
+    let in_play: Vec<Card> = solution
+        .as_cards()
+        .into_iter()
+        .chain(state.players.iter().flat_map(|p| p.cards.iter().cloned()))
+        .collect();
+
+    let mut unused = state.settings.deck.cards();
+    unused.retain(|card| !in_play.contains(card));
+
+    Ok((ContentType::JSON, to_string(&unused).unwrap()))
+}
+
+#[get("/admin/audit")]
+fn get_audit(
+    _admin: AdminToken,
+    audit: &State<AuditLog>,
+) -> Result<(ContentType, String), ApiError> {
+    Ok((ContentType::JSON, to_string(&audit.entries()).unwrap()))
+}
+
+/// Hands the turn on when the active player has gone quiet and there is no
+/// turn timer to do it. A disproof still being chosen is settled first.
+#[post("/game/force-next-turn")]
+fn force_next_turn(
+    _admin: AdminToken,
+    game_state: &State<SharedGame>,
+    audit: &State<AuditLog>,
+) -> Result<(ContentType, String), ApiError> {
+    let mut state = game_state.lock()?;
+
+    if state.phase != Phase::InProgress {
+        return Err(ApiError::new(Status::Conflict, "game not in progress")
+            .with_details(json!({ "phase": state.phase })));
+    }
+
+    let skipped = state
+        .active_player()
+        .map(|index| state.players[index].name.clone());
+
+    if let Some(settled) = state.settle_pending_disproof() {
+        audit.record("disproof settled", settled);
+    }
+    state.advance_turn();
+
+    let active = state
+        .active_player()
+        .map(|index| state.players[index].name.clone());
+    audit.record(
+        "forced next turn",
+        format!(
+            "turn {}: skipped {}, now {}",
+            state.turn_number - 1,
+            skipped.as_deref().unwrap_or("nobody"),
+            active.as_deref().unwrap_or("nobody"),
+        ),
+    );
+
+    Ok((
+        ContentType::JSON,
+        to_string(&json!({ "turn_number": state.turn_number, "active_player": active })).unwrap(),
+    ))
+}
+
+#[get("/admin/export")]
+fn export_state(
+    _admin: AdminToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = game_state.lock()?;
+
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        game: state.clone(),
+        tokens: state
+            .players
+            .iter()
+            .map(|p| (p.name.clone(), p.token.clone()))
+            .collect(),
+    };
+
+    Ok((ContentType::JSON, to_string(&snapshot).unwrap()))
+}
+
+#[post("/admin/import", data = "<snapshot>")]
+fn import_state(
+    _admin: AdminToken,
+    snapshot: Json<Snapshot>,
+    game_state: &State<SharedGame>,
+) -> Result<Status, ApiError> {
+    let problems = snapshot.problems();
+    if !problems.is_empty() {
+        return Err(
+            ApiError::new(Status::UnprocessableEntity, "invalid snapshot")
+                .with_details(json!({ "problems": problems })),
+        );
+    }
+
+    let Snapshot {
+        mut game, tokens, ..
+    } = snapshot.0;
+    for player in &mut game.players {
+        player.token = tokens[&player.name].clone();
+    }
+
+    // A game that arrives finished was archived wherever it finished, and
+    // keeps the time it did.
+    let mut state = game_state.lock()?;
+    state.was_finished = game.phase == Phase::Finished;
+    *state = game;
+
+    Ok(Status::NoContent)
+}
+
+/// The deck in play, plus the names behind each index for `?repr=index`.
+#[get("/cards?<repr>")]
+fn get_cards(
+    repr: Option<Repr>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let state = &game_state.lock()?;
+
+    // Only the deck is indexed; the lists are the names for the indices.
+    let mut cards = to_value(state.settings.deck.cards()).unwrap();
+    if repr == Some(Repr::Index) {
+        index_card_names(&mut cards);
+    }
+
+    let body = json!({
+        "cards": cards,
+        "suspects": Suspect::iter().collect::<Vec<_>>(),
+        "weapons": Weapon::iter().collect::<Vec<_>>(),
+        "rooms": Room::iter().collect::<Vec<_>>(),
+    });
+
+    Ok(encoding.encode(&body))
+}
+
+/// Every suspect, weapon and room in declaration order, the order hands
+/// are best laid out in, with the index `?repr=index` uses for each.
+#[get("/cards/order")]
+fn get_card_order() -> (ContentType, String) {
+    fn indexed<T: Serialize>(cards: impl Iterator<Item = T>) -> Vec<Value> {
+        cards
+            .enumerate()
+            .map(|(index, card)| json!({ "index": index, "name": card }))
+            .collect()
+    }
+
+    let body = json!({
+        "suspects": indexed(Suspect::iter()),
+        "weapons": indexed(Weapon::iter()),
+        "rooms": indexed(Room::iter()),
+    });
+
+    (ContentType::JSON, to_string(&body).unwrap())
+}
+
+/// The suggestions made, as the caller may see them, and the turns on
+/// which the active player declined to suggest, in the order they
+/// happened. Indices elsewhere, such as `/game/passes/<index>` and the
+/// events, count the suggestions alone.
+#[get("/game/history?<repr>")]
+fn get_history(
+    repr: Option<Repr>,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let (state, _) = &game_state.snapshot()?;
+
+    let view = state.view_for(state.viewer_name(token.as_ref()), admin.is_some());
+
+    Ok(repr.unwrap_or_default().encode(encoding, &view.history))
+}
+
+/// Suggestions still waiting for their disprover to pick a card.
+#[get("/game/pending-disproofs")]
+fn get_pending_disproofs(
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let pending: Vec<Value> = state
+        .history
+        .iter()
+        .enumerate()
+        .filter_map(|(index, record)| match &record.outcome {
+            DisproofOutcome::Pending { by } => Some(json!({
+                "index": index,
+                "suggested_by": record.suggested_by,
+                "awaiting": by,
+            })),
+            _ => None,
+        })
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&pending).unwrap()))
+}
+
+#[get("/game/passes/<index>")]
+fn get_passes(
+    index: usize,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let record = state.history.get(index).ok_or(Status::NotFound)?;
+
+    let body = json!({
+        "passed": record.passed,
+        "disproved_by": record.outcome.disprover(),
+    });
+
+    Ok((ContentType::JSON, to_string(&body).unwrap()))
+}
+
+#[get("/game/turn-order?<from>")]
+fn get_turn_order(
+    from: &str,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    let order = state.turn_order_from(from).ok_or_else(|| {
+        ApiError::new(Status::NotFound, "player is not in the turn order")
+            .with_details(json!({ "from": from }))
+    })?;
+
+    Ok((ContentType::JSON, to_string(&order).unwrap()))
+}
+
+/// The suggestions naming `room`, each with its outcome as the viewer may
+/// see it.
+#[get("/game/suggestions-in/<room>?<repr>")]
+fn get_suggestions_in(
+    room: &str,
+    repr: Option<Repr>,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let room: Room = room.parse().map_err(|_| {
+        ApiError::new(Status::NotFound, "unknown room").with_details(json!({ "room": room }))
+    })?;
+
+    let (state, _) = &game_state.snapshot()?;
+
+    let view = state.view_for(state.viewer_name(token.as_ref()), admin.is_some());
+    let suggestions: Vec<PublicSuggestion> = view
+        .history
+        .into_iter()
+        .filter_map(HistoryEntry::suggestion)
+        .filter(|suggestion| suggestion.suggestion.room == room)
+        .collect();
+
+    Ok(repr.unwrap_or_default().encode(encoding, &suggestions))
+}
+
+#[get("/game/suggestions-by/<name>?<repr>")]
+fn get_suggestions_by(
+    name: &str,
+    repr: Option<Repr>,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let (state, _) = &game_state.snapshot()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    let player = &state.players[index].name;
+
+    let view = state.view_for(state.viewer_name(token.as_ref()), admin.is_some());
+    let suggestions: Vec<PublicSuggestion> = view
+        .history
+        .into_iter()
+        .filter_map(HistoryEntry::suggestion)
+        .filter(|suggestion| suggestion.suggested_by == player)
+        .collect();
+
+    Ok(repr.unwrap_or_default().encode(encoding, &suggestions))
+}
+
+/// The events after `seq`, for a client catching up after a reconnect.
+/// Cards shown privately stay hidden from everyone else.
+#[get("/game/events/since/<seq>")]
+fn get_events_since(
+    seq: u64,
+    token: Option<PlayerToken>,
+    admin: Option<AdminToken>,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let (state, _) = &game_state.snapshot()?;
+
+    let viewer = state.viewer_name(token.as_ref());
+    let events = state.events_since(seq, viewer, admin.is_some());
+
+    Ok((ContentType::JSON, to_string(&events).unwrap()))
+}
+
+/// Where each suspect's token is, played or not. Tokens are only placed
+/// once the game has started.
+#[get("/game/suspect-tokens")]
+fn get_suspect_tokens(game_state: &State<SharedGame>) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    if state.phase == Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game not started"));
+    }
+
+    let tokens: Vec<Value> = state
+        .settings
+        .deck
+        .suspects
+        .iter()
+        .map(|suspect| {
+            let player = state
+                .players
+                .iter()
+                .find(|player| player.character.as_ref() == Some(suspect))
+                .map(|player| player.name.as_str());
+            json!({
+                "suspect": suspect,
+                "player": player,
+                "location": state.suspect_location(suspect),
+            })
+        })
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&tokens).unwrap()))
+}
+
+/// For commentary: how many solution candidates each player has left per
+/// category, from what each of them knows. Admin only, since it draws on
+/// every player's private notes.
+#[get("/game/analytics/candidates")]
+fn get_candidate_counts(
+    _admin: AdminToken,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, String), ApiError> {
+    let state = &game_state.lock()?;
+
+    if state.phase == Phase::Lobby {
+        return Err(ApiError::new(Status::Conflict, "game not started"));
+    }
+
+    let counts: Vec<Value> = state
+        .players
+        .iter()
+        .enumerate()
+        .map(|(index, player)| {
+            json!({
+                "player": player.name,
+                "suspect": state.candidate_count(index, "suspect"),
+                "weapon": state.candidate_count(index, "weapon"),
+                "room": state.candidate_count(index, "room"),
+            })
+        })
+        .collect();
+
+    Ok((ContentType::JSON, to_string(&counts).unwrap()))
+}
+
+/// The cards `name` is publicly known not to hold, from the suggestions
+/// they passed on, in deck order.
+#[get("/game/known-absences/<name>?<repr>")]
+fn get_known_absences(
+    name: &str,
+    repr: Option<Repr>,
+    encoding: Encoding,
+    game_state: &State<SharedGame>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let state = &game_state.lock()?;
+
+    let index = state.player_index(name).ok_or(Status::NotFound)?;
+    let player = &state.players[index].name;
+
+    let passed_on: Vec<Card> = state
+        .history
+        .iter()
+        .filter(|record| record.passed.contains(player))
+        .flat_map(|record| record.suggestion.as_cards())
+        .collect();
+    let absent: Vec<Card> = state
+        .settings
+        .deck
+        .cards()
+        .into_iter()
+        .filter(|card| passed_on.contains(card))
+        .collect();
+
+    Ok(repr.unwrap_or_default().encode(encoding, &absent))
 }
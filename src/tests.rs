@@ -0,0 +1,3180 @@
+//! The routes end to end, through a local client.
+
+use std::sync::Barrier;
+
+use rocket::{
+    http::{Accept, Header, MediaType},
+    local::blocking::{Client, LocalRequest},
+};
+
+use super::*;
+
+const ADMIN: &str = "test-admin-token";
+
+/// A hand each, with Orchid, the Wrench and the Study left for the envelope.
+/// Joined in this order, alice plays Plum, bob Green and carol Mustard.
+const THREE_HANDS: [(&str, &[&str]); 3] = [
+    (
+        "alice",
+        &[
+            "Plum",
+            "Green",
+            "Candlestick",
+            "LeadPipe",
+            "Kitchen",
+            "Hall",
+        ],
+    ),
+    (
+        "bob",
+        &[
+            "Mustard",
+            "Peacock",
+            "Dagger",
+            "Lounge",
+            "Ballroom",
+            "Conservatory",
+        ],
+    ),
+    (
+        "carol",
+        &[
+            "Scarlett",
+            "Rope",
+            "Revolver",
+            "DiningRoom",
+            "Library",
+            "BilliardRoom",
+        ],
+    ),
+];
+
+/// As `THREE_HANDS`, between two players.
+const TWO_HANDS: [(&str, &[&str]); 2] = [
+    (
+        "alice",
+        &[
+            "Plum",
+            "Green",
+            "Mustard",
+            "Candlestick",
+            "LeadPipe",
+            "Dagger",
+            "Kitchen",
+            "Hall",
+            "Lounge",
+        ],
+    ),
+    (
+        "bob",
+        &[
+            "Peacock",
+            "Scarlett",
+            "Rope",
+            "Revolver",
+            "Ballroom",
+            "Conservatory",
+            "DiningRoom",
+            "Library",
+            "BilliardRoom",
+        ],
+    ),
+];
+
+fn client() -> Client {
+    client_with(json!({}))
+}
+
+/// A server with an admin token and whatever else `config` sets.
+fn client_with(config: Value) -> Client {
+    Client::tracked(server_with(config)).unwrap()
+}
+
+fn server_with(config: Value) -> Rocket<Build> {
+    let figment = rocket::Config::figment()
+        .merge(("admin_token", ADMIN))
+        .merge(("log_level", "off"))
+        .merge(rocket::figment::providers::Serialized::defaults(config));
+
+    server(rocket::custom(figment))
+}
+
+fn bearer(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {token}"))
+}
+
+fn admin() -> Header<'static> {
+    Header::new("X-Admin-Token", ADMIN)
+}
+
+/// The `action_seq` the game is at, echoed as a client would.
+fn seq(client: &Client) -> Header<'static> {
+    Header::new("X-Action-Seq", game(client)["action_seq"].to_string())
+}
+
+/// The status and JSON body of a response; `null` when there is no body.
+fn send(request: LocalRequest<'_>) -> (Status, Value) {
+    let response = request.dispatch();
+    (
+        response.status(),
+        response.into_json().unwrap_or(Value::Null),
+    )
+}
+
+fn card(name: &str) -> Value {
+    to_value(name.parse::<Card>().unwrap()).unwrap()
+}
+
+fn cards(names: &[&str]) -> Value {
+    names.iter().map(|name| card(name)).collect()
+}
+
+fn join(client: &Client, name: &str) -> String {
+    let (status, body) = send(client.post(format!("/players/{name}")));
+    assert_eq!(status, Status::Created, "{body}");
+
+    body["token"].as_str().unwrap().to_owned()
+}
+
+fn start(client: &Client, host: &str, settings: Value) -> Value {
+    let (status, body) = send(client.post("/game").header(bearer(host)).json(&settings));
+    assert_eq!(status, Status::Created, "{body}");
+
+    body
+}
+
+/// Joins everyone in `hands`, in order, and starts the game with exactly
+/// those hands.
+fn seated<const N: usize>(
+    client: &Client,
+    hands: &[(&str, &[&str]); N],
+    settings: Value,
+) -> [String; N] {
+    let tokens = hands.map(|(name, _)| join(client, name));
+
+    let mut body = settings;
+    body["hands"] = hands
+        .iter()
+        .map(|(name, hand)| (name.to_string(), cards(hand)))
+        .collect::<Map<_, _>>()
+        .into();
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&tokens[0]))
+            .header(admin())
+            .json(&body),
+    );
+    assert_eq!(status, Status::Created, "{body}");
+
+    tokens
+}
+
+/// The game as an admin sees it.
+fn game(client: &Client) -> Value {
+    send(client.get("/game").header(admin())).1
+}
+
+/// Works on the game directly, to stand in for time passing.
+fn with_game<T>(client: &Client, change: impl FnOnce(&mut GameState) -> T) -> T {
+    let game = client.rocket().state::<SharedGame>().unwrap();
+    change(&mut game.state.lock().unwrap())
+}
+
+fn audit_actions(client: &Client) -> Vec<String> {
+    let audit = client.rocket().state::<AuditLog>().unwrap();
+    audit
+        .entries()
+        .into_iter()
+        .map(|entry| entry.action)
+        .collect()
+}
+
+fn move_to(client: &Client, token: &str, room: &str) {
+    let (status, body) = send(
+        client
+            .post("/game/move")
+            .header(bearer(token))
+            .header(seq(client))
+            .json(&json!({ "room": room })),
+    );
+    assert_eq!(status, Status::NoContent, "{body}");
+}
+
+fn suggest(client: &Client, token: &str, trio: [&str; 3]) -> (Status, Value) {
+    let [suspect, weapon, room] = trio;
+    send(
+        client
+            .post("/suggest")
+            .header(bearer(token))
+            .header(seq(client))
+            .json(&json!({ "suspect": suspect, "weapon": weapon, "room": room })),
+    )
+}
+
+fn accuse(client: &Client, token: &str, trio: [&str; 3]) -> (Status, Value) {
+    let [suspect, weapon, room] = trio;
+    send(
+        client
+            .post("/game/accuse")
+            .header(bearer(token))
+            .header(seq(client))
+            .json(&json!({ "suspect": suspect, "weapon": weapon, "room": room })),
+    )
+}
+
+fn end_turn(client: &Client, token: &str) {
+    let (status, body) = send(
+        client
+            .post("/game/end-turn")
+            .header(bearer(token))
+            .header(seq(client)),
+    );
+    assert_eq!(status, Status::NoContent, "{body}");
+}
+
+const SOLUTION: [&str; 3] = ["Orchid", "Wrench", "Study"];
+
+#[test]
+fn host_hands_over_the_host_role() {
+    let client = client();
+    let alice = join(&client, "alice");
+    let bob = join(&client, "bob");
+
+    let (status, _) = send(
+        client
+            .post("/game/host")
+            .header(bearer(&alice))
+            .json(&json!({ "name": "bob" })),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(game(&client)["host"], "bob");
+
+    // The old host lost the role along with its routes.
+    let (status, _) = send(client.post("/game").header(bearer(&alice)));
+    assert_eq!(status, Status::Forbidden);
+    start(&client, &bob, json!({}));
+}
+
+#[test]
+fn only_the_host_hands_over_the_host_role() {
+    let client = client();
+    join(&client, "alice");
+    let bob = join(&client, "bob");
+
+    let (status, body) = send(
+        client
+            .post("/game/host")
+            .header(bearer(&bob))
+            .json(&json!({ "name": "bob" })),
+    );
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(body["error"], "only the host can hand over the host role");
+    assert_eq!(game(&client)["host"], "alice");
+
+    let (status, _) = send(client.post("/game/host").json(&json!({ "name": "bob" })));
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[test]
+fn host_kicks_a_player_from_the_lobby() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let (status, _) = send(client.post("/game/kick/bob").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+
+    let (_, players) = send(client.get("/players"));
+    assert_eq!(players.as_array().unwrap().len(), 1);
+
+    // Bob's character is free again.
+    join(&client, "carol");
+    let (_, carol) = send(client.get("/players/carol"));
+    assert_eq!(carol["character"], "Green");
+}
+
+#[test]
+fn host_cannot_kick_themselves() {
+    let client = client();
+    let alice = join(&client, "alice");
+
+    let (status, body) = send(client.post("/game/kick/alice").header(bearer(&alice)));
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(body["error"], "cannot kick the host");
+}
+
+#[test]
+fn nobody_is_kicked_once_the_game_started() {
+    let client = client();
+    let [alice, ..] = seated(&client, &TWO_HANDS, json!({}));
+
+    let (status, body) = send(client.post("/game/kick/bob").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "game already started");
+    assert_eq!(body["details"]["phase"], "InProgress");
+}
+
+#[test]
+fn only_the_host_kicks() {
+    let client = client();
+    join(&client, "alice");
+    let bob = join(&client, "bob");
+
+    let (status, body) = send(client.post("/game/kick/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(body["error"], "only the host can kick players");
+}
+
+#[test]
+fn names_collide_across_accents_and_case() {
+    let client = client();
+    join(&client, "Jos%C3%A9");
+
+    // jose, JOSÉ, and José spelt with a combining accent.
+    for name in ["jose", "JOS%C3%89", "Jose%CC%81"] {
+        let (status, body) = send(client.post(format!("/players/{name}")));
+        assert_eq!(status, Status::Conflict, "{name}");
+        assert_eq!(body["details"]["existing"], "José");
+        assert_eq!(body["details"]["key"], "jose");
+    }
+
+    join(&client, "Josef");
+}
+
+#[test]
+fn pausing_blocks_actions_until_resumed() {
+    let client = client();
+    let [alice, _, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, _) = send(client.post("/game/pause").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+
+    let blocked = [
+        send(
+            client
+                .post("/game/move")
+                .header(bearer(&alice))
+                .header(seq(&client))
+                .json(&json!({ "room": "Kitchen" })),
+        ),
+        suggest(&client, &alice, ["Orchid", "Wrench", "Kitchen"]),
+        accuse(&client, &alice, SOLUTION),
+        send(
+            client
+                .post("/game/end-turn")
+                .header(bearer(&alice))
+                .header(seq(&client)),
+        ),
+    ];
+    for (status, body) in blocked {
+        assert_eq!(status, Status::Conflict);
+        assert_eq!(body["error"], "game paused");
+    }
+
+    // Reads still work.
+    assert_eq!(game(&client)["paused"], true);
+
+    let (status, _) = send(client.post("/game/resume").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+    move_to(&client, &alice, "Kitchen");
+    end_turn(&client, &alice);
+}
+
+#[test]
+fn pausing_stops_the_turn_timer() {
+    let client = client();
+    let [alice, _, _] = seated(&client, &THREE_HANDS, json!({ "turn_timer_secs": 60 }));
+
+    send(client.post("/game/pause").header(bearer(&alice)));
+    // The turn began just as the game was paused, two minutes ago.
+    with_game(&client, |state| {
+        state.paused_at -= 120;
+        state.turn_started_at -= 120;
+    });
+    assert_eq!(game(&client)["turn_number"], 1);
+
+    send(client.post("/game/resume").header(bearer(&alice)));
+    assert_eq!(game(&client)["turn_number"], 1);
+    assert_eq!(game(&client)["active_player"], "alice");
+}
+
+#[test]
+fn pause_and_resume_explain_refusals() {
+    let client = client();
+    let alice = join(&client, "alice");
+    let bob = join(&client, "bob");
+
+    let (status, body) = send(client.post("/game/pause").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["details"]["phase"], "Lobby");
+
+    start(&client, &alice, json!({}));
+    let (status, body) = send(client.post("/game/pause").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(body["error"], "only the host can pause or resume the game");
+
+    let (status, body) = send(client.post("/game/resume").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "game not paused");
+}
+
+#[test]
+fn theme_is_echoed_back() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let created = start(&client, &alice, json!({ "theme": "vintage" }));
+    assert_eq!(created["theme"], "vintage");
+    assert_eq!(send(client.get("/game")).1["theme"], "vintage");
+}
+
+#[test]
+fn unknown_theme_is_refused() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .json(&json!({ "theme": "neon" })),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "unknown theme");
+    assert_eq!(body["details"]["allowed"], json!(THEMES));
+}
+
+#[test]
+fn fairness_of_five_and_six_hands() {
+    for (players, spread) in [(5, 1), (6, 0)] {
+        let client = client();
+        let tokens: Vec<String> = (1..=players)
+            .map(|seat| join(&client, &format!("player{seat}")))
+            .collect();
+
+        let fairness = start(&client, &tokens[0], json!({}))["fairness"].clone();
+        let (min, max) = (&fairness["min_hand_size"], &fairness["max_hand_size"]);
+        assert_eq!(max.as_u64().unwrap() - min.as_u64().unwrap(), spread);
+        assert_eq!(fairness["even"], spread == 0);
+    }
+}
+
+#[test]
+fn suggestions_filtered_by_player() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]);
+    end_turn(&client, &alice);
+    move_to(&client, &bob, "Lounge");
+    suggest(&client, &bob, ["Scarlett", "Wrench", "Lounge"]);
+
+    let (_, by_alice) = send(client.get("/game/suggestions-by/alice"));
+    assert_eq!(by_alice.as_array().unwrap().len(), 1);
+    assert_eq!(by_alice[0]["suggested_by"], "alice");
+    assert_eq!(by_alice[0]["room"], "Kitchen");
+
+    let (_, by_bob) = send(client.get("/game/suggestions-by/bob"));
+    assert_eq!(by_bob.as_array().unwrap().len(), 1);
+    assert_eq!(by_bob[0]["suggested_by"], "bob");
+}
+
+#[test]
+fn every_start_problem_is_reported_together() {
+    let client = client();
+    let alice = join(&client, "alice");
+
+    let deck = json!({ "suspects": ["Green", "Mustard"] });
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .json(&json!({ "deck": deck })),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(
+        body["details"]["problems"],
+        json!([
+            "1 players joined, need 2 to 6",
+            "alice plays Plum, who is not in the deck",
+        ])
+    );
+}
+
+#[test]
+fn disproof_direction_picks_the_disprover() {
+    for (direction, by, shown) in [
+        ("Clockwise", "bob", "Mustard"),
+        ("Counterclockwise", "carol", "Rope"),
+    ] {
+        let client = client();
+        let [alice, _, _] = seated(
+            &client,
+            &THREE_HANDS,
+            json!({ "disproof_direction": direction }),
+        );
+
+        move_to(&client, &alice, "Kitchen");
+        let (_, result) = suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+        assert_eq!(result["outcome"]["by"], by, "{direction}");
+        assert_eq!(result["outcome"]["card"], card(shown), "{direction}");
+    }
+}
+
+#[test]
+fn moves_are_recorded_with_their_turns() {
+    let client = client();
+    let [alice, bob, carol] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    end_turn(&client, &alice);
+    end_turn(&client, &bob);
+    end_turn(&client, &carol);
+    move_to(&client, &alice, "Hall");
+
+    let (_, moves) = send(client.get("/game/moves/alice"));
+    assert_eq!(
+        moves,
+        json!([
+            { "player": "alice", "turn": 1, "room": "Kitchen" },
+            { "player": "alice", "turn": 4, "room": "Hall" },
+        ])
+    );
+}
+
+#[test]
+fn players_in_message_pack() {
+    let client = client();
+    join(&client, "alice");
+
+    let response = client
+        .get("/players")
+        .header(Accept::new([
+            MediaType::new("application", "msgpack").into()
+        ]))
+        .dispatch();
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("application", "msgpack"))
+    );
+    let players: Value = rmp_serde::from_slice(&response.into_bytes().unwrap()).unwrap();
+
+    assert_eq!(players, send(client.get("/players")).1);
+    assert_eq!(players[0]["name"], "alice");
+}
+
+#[test]
+fn sequential_dealer_deals_in_catalogue_order() {
+    let client = client_with(json!({ "dealer": "sequential" }));
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    start(&client, &alice, json!({}));
+
+    let state = game(&client);
+    let solution: Vec<Card> = serde_json::from_value::<Suggestion>(state["solution"].clone())
+        .unwrap()
+        .as_cards()
+        .into();
+    let mut dealt = Deck::default().cards();
+    dealt.retain(|card| !solution.contains(card));
+
+    for (seat, player) in state["players"].as_array().unwrap().iter().enumerate() {
+        let expected: Vec<&Card> = dealt.iter().skip(seat).step_by(2).collect();
+        assert_eq!(player["cards"], json!(expected));
+    }
+}
+
+#[test]
+fn eliminated_players_with_their_accusations() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    accuse(&client, &alice, ["Plum", "Rope", "Hall"]);
+    accuse(&client, &bob, ["Green", "Dagger", "Study"]);
+
+    let (_, eliminated) = send(client.get("/game/eliminated"));
+    assert_eq!(
+        eliminated,
+        json!([
+            {
+                "name": "alice",
+                "reason": "wrong_accusation",
+                "accusation": { "suspect": "Plum", "weapon": "Rope", "room": "Hall" },
+            },
+            {
+                "name": "bob",
+                "reason": "wrong_accusation",
+                "accusation": { "suspect": "Green", "weapon": "Dagger", "room": "Study" },
+            },
+        ])
+    );
+    assert_eq!(game(&client)["winner"], "carol");
+}
+
+#[test]
+fn waiting_too_long_for_the_game_is_a_503() {
+    let client = client_with(json!({ "request_timeout_ms": 50 }));
+
+    let shared = client.rocket().state::<SharedGame>().unwrap();
+    let held = shared.state.lock().unwrap();
+    let response = client.get("/game").dispatch();
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(response.headers().get_one("Retry-After"), Some("1"));
+    drop(response);
+    drop(held);
+
+    assert!(audit_actions(&client).contains(&"timeout".to_owned()));
+    assert!(!shared.state.is_poisoned());
+    assert_eq!(send(client.get("/game")).0, Status::Ok);
+}
+
+/// Reads the game slowly, as a handler waiting on a database would.
+#[get("/slow/<millis>")]
+fn slow_read(millis: u64, game_state: &State<SharedGame>) -> Result<String, ApiError> {
+    let state = game_state.lock()?;
+    thread::sleep(Duration::from_millis(millis));
+
+    Ok(state.settings.theme.clone())
+}
+
+/// Changes the game slowly, holding it all the while.
+#[post("/slow/<millis>")]
+fn slow_write(millis: u64, game_state: &State<SharedGame>) -> Result<Status, ApiError> {
+    let mut state = game_state.lock()?;
+    thread::sleep(Duration::from_millis(millis));
+    state.settings.theme = "slow".to_owned();
+
+    Ok(Status::NoContent)
+}
+
+#[test]
+fn a_handler_running_over_is_a_503() {
+    let rocket =
+        server_with(json!({ "request_timeout_ms": 50 })).mount("/", routes![slow_read, slow_write]);
+    let client = Client::tracked(rocket).unwrap();
+
+    assert_eq!(send(client.get("/slow/0")).0, Status::Ok);
+    let (status, body) = send(client.get("/slow/100"));
+    assert_eq!(status, Status::ServiceUnavailable);
+    assert_eq!(body["error"], "request took too long");
+
+    // The write went through, so it is answered as such.
+    let (status, _) = send(client.post("/slow/100"));
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(
+        with_game(&client, |state| state.settings.theme.clone()),
+        "slow"
+    );
+
+    let slow = audit_actions(&client)
+        .into_iter()
+        .filter(|action| action == "slow request")
+        .count();
+    assert_eq!(slow, 2);
+    assert!(
+        !client
+            .rocket()
+            .state::<SharedGame>()
+            .unwrap()
+            .state
+            .is_poisoned()
+    );
+}
+
+#[test]
+fn repeated_suggestion_is_flagged() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    let (_, first) = suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]);
+    assert_eq!(first["duplicate"], false);
+    end_turn(&client, &alice);
+
+    move_to(&client, &bob, "Kitchen");
+    let (_, second) = suggest(&client, &bob, ["Orchid", "Rope", "Kitchen"]);
+    assert_eq!(second["duplicate"], true);
+}
+
+#[test]
+fn oversized_deck_still_has_a_three_card_envelope() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    join(&client, "carol");
+
+    // Every suspect, weapon and room, plus nine clock cards: 30 in all.
+    let created = start(&client, &alice, json!({ "deck": { "clocks": 9 } }));
+    assert_eq!(created["fairness"]["min_hand_size"], 9);
+    assert_eq!(created["fairness"]["max_hand_size"], 9);
+    assert_eq!(created["fairness"]["even"], true);
+
+    let state = game(&client);
+    let solution: Suggestion = serde_json::from_value(state["solution"].clone()).unwrap();
+    let dealt: Vec<Value> = state["players"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|player| player["cards"].as_array().unwrap().clone())
+        .collect();
+    for card in solution.as_cards() {
+        assert!(!dealt.contains(&json!(card)));
+    }
+    assert_eq!(dealt.len(), 27);
+}
+
+#[test]
+fn fast_preset_expands_to_its_settings() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    // Team play isn't there to expand into, so `team` is listed but refused.
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .json(&json!({ "preset": "team" })),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "preset not available");
+
+    start(
+        &client,
+        &alice,
+        json!({ "preset": "fast", "disproof_timeout_secs": 30 }),
+    );
+
+    let (_, config) = send(client.get("/game/config"));
+    assert_eq!(config["turn_timer_secs"], 60);
+    assert_eq!(config["require_move_before_suggest"], true);
+    assert_eq!(config["disproof_timeout_secs"], 30);
+    assert_eq!(config["open_info"], false);
+
+    let (_, presets) = send(client.get("/rules/presets"));
+    let preset = |name: &str| {
+        presets
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|preset| preset["name"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(preset("fast")["available"], true);
+    assert_eq!(preset("fast")["settings"]["disproof_timeout_secs"], 15);
+    assert_eq!(preset("team")["available"], false);
+}
+
+#[test]
+fn solution_oracle_for_admins() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (_, body) = send(client.get("/game/is-solution?card=Orchid").header(admin()));
+    assert_eq!(body["in_solution"], true);
+    let (_, body) = send(client.get("/game/is-solution?card=Plum").header(admin()));
+    assert_eq!(body["in_solution"], false);
+
+    let (status, _) = send(client.get("/game/is-solution?card=Orchid"));
+    assert_eq!(status, Status::Unauthorized);
+}
+
+#[test]
+fn colors_are_valid_and_unique() {
+    let client = client();
+    let (status, _) = send(client.post("/players/alice?color=Red"));
+    assert_eq!(status, Status::Created);
+    let bob = join(&client, "bob");
+
+    let set_color = |color: &str| {
+        send(
+            client
+                .patch("/players/bob/color")
+                .header(bearer(&bob))
+                .json(&json!({ "color": color })),
+        )
+    };
+
+    let (status, body) = set_color("RED");
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["details"]["taken_by"], "alice");
+
+    let (status, body) = set_color("chartreuse");
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "invalid color");
+
+    let (status, _) = set_color("#00F");
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(send(client.get("/players/bob")).1["color"], "#00f");
+}
+
+#[test]
+fn snapshot_round_trips() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    send(
+        client
+            .post("/game/notebook/alice/mark")
+            .header(bearer(&alice))
+            .json(&json!({ "card": card("Rope"), "mark": "maybe" })),
+    );
+    move_to(&client, &alice, "Kitchen");
+
+    let (_, exported) = send(client.get("/admin/export").header(admin()));
+    let (status, _) = send(client.delete("/game").header(admin()));
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(game(&client)["players"], json!([]));
+
+    let (status, _) = send(client.post("/admin/import").header(admin()).json(&exported));
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(
+        send(client.get("/admin/export").header(admin())).1,
+        exported
+    );
+
+    // The tokens came back too.
+    let (_, notebook) = send(client.get("/game/notebook/alice").header(bearer(&alice)));
+    assert_eq!(
+        notebook["marks"],
+        json!([{ "card": card("Rope"), "mark": "maybe" }])
+    );
+}
+
+#[test]
+fn broken_snapshot_is_refused() {
+    let client = client();
+    seated(&client, &TWO_HANDS, json!({}));
+
+    let (_, mut exported) = send(client.get("/admin/export").header(admin()));
+    exported["version"] = 2.into();
+    exported["tokens"] = json!({});
+
+    let (status, body) = send(client.post("/admin/import").header(admin()).json(&exported));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(
+        body["details"]["problems"],
+        json!([
+            "snapshot version 2 is not 1",
+            "no token for alice",
+            "no token for bob",
+        ])
+    );
+}
+
+#[test]
+fn importing_a_finished_game_keeps_its_record() {
+    let client = client();
+    let [alice, ..] = seated(&client, &TWO_HANDS, json!({}));
+    accuse(&client, &alice, SOLUTION);
+
+    with_game(&client, |state| state.finished_at -= 100);
+    let (_, exported) = send(client.get("/admin/export").header(admin()));
+    let finished_at = exported["game"]["finished_at"].as_u64().unwrap();
+
+    // Restored over a fresh lobby, as after a restart.
+    send(client.delete("/game").header(admin()));
+    send(client.post("/admin/import").header(admin()).json(&exported));
+
+    assert_eq!(send(client.get("/stats")).1["games_played"], 1);
+    assert_eq!(with_game(&client, |state| state.finished_at), finished_at);
+}
+
+#[test]
+fn solution_and_deal_are_seeded_separately() {
+    let deal = |deal_seed: u64| {
+        let client = client();
+        let alice = join(&client, "alice");
+        join(&client, "bob");
+        let settings = json!({ "solution_seed": 7, "deal_seed": deal_seed });
+        let (_, created) = send(
+            client
+                .post("/game")
+                .header(bearer(&alice))
+                .header(admin())
+                .json(&settings),
+        );
+        assert_eq!(created["solution_seed"], 7);
+        assert_eq!(created["deal_seed"], deal_seed);
+
+        let state = game(&client);
+        (
+            state["solution"].clone(),
+            state["players"][0]["cards"].clone(),
+        )
+    };
+
+    let (solution, hand) = deal(1);
+    assert_eq!(deal(1), (solution.clone(), hand.clone()));
+    let (other_solution, other_hand) = deal(2);
+    assert_eq!(other_solution, solution);
+    assert_ne!(other_hand, hand);
+}
+
+#[test]
+fn seeds_are_echoed_to_admins_only() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let created = start(&client, &alice, json!({ "solution_seed": 7 }));
+    assert!(created.get("solution_seed").is_none());
+    assert!(created.get("deal_seed").is_none());
+}
+
+#[test]
+fn own_character_may_be_barred_from_suggestions() {
+    for (forbidden, expected) in [(true, Status::UnprocessableEntity), (false, Status::Ok)] {
+        let client = client();
+        let [alice, ..] = seated(
+            &client,
+            &THREE_HANDS,
+            json!({ "forbid_self_suspect": forbidden }),
+        );
+
+        move_to(&client, &alice, "Kitchen");
+        let (status, body) = suggest(&client, &alice, ["Plum", "Rope", "Kitchen"]);
+        assert_eq!(status, expected, "{body}");
+        if forbidden {
+            assert_eq!(body["error"], "cannot suggest your own character");
+            assert_eq!(body["details"]["character"], "Plum");
+        }
+    }
+}
+
+#[test]
+fn anonymous_view_is_cached_until_the_game_changes() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let view = || client.get("/game").dispatch().into_bytes().unwrap();
+    let first = view();
+    assert_eq!(view(), first);
+
+    move_to(&client, &alice, "Kitchen");
+    let moved = view();
+    assert_ne!(moved, first);
+    assert_eq!(view(), moved);
+}
+
+#[test]
+fn notebook_marks_are_private() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let mark = |mark: Value| {
+        send(
+            client
+                .post("/game/notebook/alice/mark")
+                .header(bearer(&alice))
+                .json(&json!({ "card": card("Rope"), "mark": mark })),
+        )
+    };
+    assert_eq!(mark(json!({ "held_by": "carol" })).0, Status::NoContent);
+
+    let (_, notebook) = send(client.get("/game/notebook/alice").header(bearer(&alice)));
+    assert_eq!(
+        notebook["marks"],
+        json!([{ "card": card("Rope"), "mark": { "held_by": "carol" } }])
+    );
+
+    // Marking a card again replaces its mark; null clears it.
+    mark(json!("maybe"));
+    mark(Value::Null);
+    let (_, notebook) = send(client.get("/game/notebook/alice").header(bearer(&alice)));
+    assert_eq!(notebook["marks"], json!([]));
+
+    let (status, _) = send(client.get("/game/notebook/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+    let (status, _) = send(
+        client
+            .post("/game/notebook/alice/mark")
+            .header(bearer(&bob))
+            .json(&json!({ "card": card("Rope"), "mark": "maybe" })),
+    );
+    assert_eq!(status, Status::Forbidden);
+}
+
+#[test]
+fn one_suggestion_per_turn() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    assert_eq!(
+        suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]).0,
+        Status::Ok
+    );
+
+    let (status, body) = suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "already suggested this turn");
+}
+
+#[test]
+fn tokens_start_on_their_squares() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (_, players) = send(client.get("/players"));
+    let locations: Vec<&Value> = players
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|player| &player["location"])
+        .collect();
+    assert_eq!(locations, ["PlumStart", "GreenStart", "MustardStart"]);
+}
+
+#[test]
+fn practice_hints_who_might_disprove() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({ "practice": true }));
+
+    let (status, hints) = send(
+        client
+            .get("/game/practice/hypothetical?suspect=Mustard&weapon=Rope&room=Kitchen")
+            .header(bearer(&alice)),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(
+        hints,
+        json!([
+            { "card": card("Mustard"), "possible_holders": ["bob", "carol"] },
+            { "card": card("Rope"), "possible_holders": ["bob", "carol"] },
+            { "card": card("Kitchen"), "possible_holders": [] },
+        ])
+    );
+}
+
+#[test]
+fn practice_hints_need_a_practice_game() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(
+        client
+            .get("/game/practice/hypothetical?suspect=Mustard&weapon=Rope&room=Kitchen")
+            .header(bearer(&alice)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "not a practice game");
+}
+
+/// Deals one card twice the first time it is asked, then deals properly.
+struct FaultyDealer(AtomicU64);
+
+impl Dealer for FaultyDealer {
+    fn order(&self, cards: &mut [Card], rng: &mut dyn RngCore) {
+        cards.shuffle(rng);
+        if self.0.fetch_add(1, Ordering::Relaxed) == 0 {
+            cards[1] = cards[0].clone();
+        }
+    }
+}
+
+#[test]
+fn broken_deal_is_retried() {
+    let mut state = GameState::new();
+    state.players = vec![Player::new("alice", None), Player::new("bob", None)];
+    state.settings.pick_seeds();
+    let deal_seed = state.settings.deal_seed.unwrap();
+
+    let audit = AuditLog::default();
+    state
+        .deal(&FaultyDealer(AtomicU64::new(0)), &audit)
+        .unwrap();
+
+    assert!(state.validate_invariants().is_empty());
+    assert_eq!(state.settings.deal_seed, Some(deal_seed.wrapping_add(1)));
+    let entries = audit.entries();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, "invalid deal");
+}
+
+#[test]
+fn actions_available_through_a_turn() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let actions = |name: &str| send(client.get(format!("/game/available-actions/{name}"))).1;
+    assert_eq!(
+        actions("alice")["actions"],
+        json!(["roll", "move", "accuse", "end-turn"])
+    );
+    assert_eq!(actions("bob")["actions"], json!([]));
+
+    move_to(&client, &alice, "Kitchen");
+    let after_move = actions("alice");
+    assert_eq!(
+        after_move["actions"],
+        json!(["suggest", "no-suggestion", "accuse", "end-turn"])
+    );
+    assert_eq!(after_move["action_seq"], 1);
+}
+
+#[test]
+fn game_ends_undecided_after_its_maximum_duration() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({ "max_duration_secs": 60 }));
+
+    assert_eq!(game(&client)["phase"], "InProgress");
+    with_game(&client, |state| state.created_at -= 60);
+
+    let state = game(&client);
+    assert_eq!(state["phase"], "Finished");
+    assert_eq!(state["timed_out"], true);
+    assert_eq!(state["winner"], Value::Null);
+    assert_eq!(send(client.get("/stats")).1["games_played"], 1);
+}
+
+#[test]
+fn chosen_disproof_goes_from_pending_to_disproved() {
+    let client = client();
+    let [alice, bob, carol] = seated(&client, &THREE_HANDS, json!({ "disproof_mode": "choose" }));
+
+    move_to(&client, &alice, "Kitchen");
+    let (_, result) = suggest(&client, &alice, ["Mustard", "Dagger", "Kitchen"]);
+    assert_eq!(
+        result["outcome"],
+        json!({ "status": "pending", "by": "bob" })
+    );
+
+    let (status, body) = send(
+        client
+            .post("/game/end-turn")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "waiting for a disproof");
+
+    let (status, _) = send(
+        client
+            .post("/game/reveal")
+            .header(bearer(&bob))
+            .json(&json!({ "card": card("Dagger") })),
+    );
+    assert_eq!(status, Status::NoContent);
+    let (_, history) = send(client.get("/game/history").header(bearer(&alice)));
+    assert_eq!(
+        history[0]["outcome"],
+        json!({ "status": "disproved", "by": "bob", "shown_to": "alice", "card": card("Dagger") })
+    );
+
+    end_turn(&client, &alice);
+    end_turn(&client, &bob);
+    end_turn(&client, &carol);
+
+    // Alice holds the Kitchen herself, so nobody else can disprove.
+    let (_, result) = suggest(&client, &alice, ["Orchid", "Wrench", "Kitchen"]);
+    assert_eq!(result["outcome"], json!({ "status": "all_passed" }));
+}
+
+#[test]
+fn resigning_hands_the_turn_on() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, _) = send(client.post("/game/resign/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, _) = send(client.post("/game/resign/alice").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+
+    let state = game(&client);
+    assert_eq!(state["active_player"], "bob");
+    assert_eq!(state["players"][0]["eliminated_reason"], "resigned");
+
+    let (status, body) = send(client.post("/game/resign/alice").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "already out of the game");
+}
+
+#[test]
+fn clock_cards_are_dealt_but_never_in_the_envelope() {
+    for _ in 0..10 {
+        let client = client();
+        let alice = join(&client, "alice");
+        join(&client, "bob");
+        start(&client, &alice, json!({ "deck": { "clocks": 3 } }));
+
+        let state = game(&client);
+        let dealt: Vec<Value> = state["players"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|player| player["cards"].as_array().unwrap().clone())
+            .collect();
+        for clock in 1..=3 {
+            assert!(dealt.contains(&json!({ "Clock": clock })));
+        }
+        assert_eq!(dealt.len(), 21);
+    }
+}
+
+#[test]
+fn turn_actions_need_the_active_players_token() {
+    let client = client();
+    let [_, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(client.post("/game/end-turn"));
+    assert_eq!(status, Status::Unauthorized);
+    assert_eq!(body["error"], "missing bearer token");
+
+    let (status, body) = send(
+        client
+            .post("/game/end-turn")
+            .header(bearer("nobody"))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Unauthorized);
+    assert_eq!(body["error"], "unknown player token");
+
+    let (status, body) = send(
+        client
+            .post("/game/end-turn")
+            .header(bearer(&bob))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "not your turn");
+}
+
+#[test]
+fn cards_set_aside_are_unused() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+    let (_, unused) = send(client.get("/game/unused-cards").header(admin()));
+    assert_eq!(unused, json!([]));
+
+    // 30 cards: three for the envelope, three set aside and eight a hand.
+    let client = self::client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    join(&client, "carol");
+    let created = start(
+        &client,
+        &alice,
+        json!({ "deck": { "clocks": 9, "set_aside": 3 } }),
+    );
+    assert_eq!(created["fairness"]["min_hand_size"], 8);
+    assert_eq!(created["fairness"]["even"], true);
+
+    let (_, unused) = send(client.get("/game/unused-cards").header(admin()));
+    assert_eq!(unused.as_array().unwrap().len(), 3);
+    let (_, everyone) = send(client.get("/game"));
+    let on_the_table = everyone["set_aside"].as_array().unwrap();
+    assert_eq!(on_the_table.len(), 3);
+    assert!(
+        on_the_table
+            .iter()
+            .all(|card| unused.as_array().unwrap().contains(card))
+    );
+
+    // Face up on the table, they count as seen by everyone.
+    assert_eq!(unseen(&client, "alice", &alice), (19, 30));
+}
+
+#[test]
+fn probability_follows_what_the_viewer_knows() {
+    let client = client();
+    let [alice, _] = seated(&client, &TWO_HANDS, json!({}));
+
+    let probability = |card: &str| {
+        send(
+            client
+                .get(format!("/game/probability/bob?card={card}"))
+                .header(bearer(&alice)),
+        )
+        .1["probability"]
+            .as_f64()
+            .unwrap()
+    };
+    assert_eq!(probability("Peacock"), 0.9);
+    assert_eq!(probability("Plum"), 0.0);
+
+    // Bob shows the Scarlett card, leaving eight of his cards unknown.
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Scarlett", "Candlestick", "Kitchen"]);
+    assert_eq!(probability("Scarlett"), 1.0);
+    assert_eq!(probability("Peacock"), 8.0 / 9.0);
+}
+
+#[test]
+fn suggestion_result_names_the_suggester() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    let (_, result) = suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]);
+    assert_eq!(result["suggested_by"], "alice");
+}
+
+#[test]
+fn stats_across_finished_games() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Scarlett", "Rope", "Kitchen"]);
+    accuse(&client, &alice, SOLUTION);
+
+    send(client.post("/game/rematch").header(bearer(&alice)));
+    let solution = game(&client)["solution"].clone();
+    let (_, result) = send(
+        client
+            .post("/game/accuse")
+            .header(bearer(&bob))
+            .header(seq(&client))
+            .json(&solution),
+    );
+    assert_eq!(result["correct"], true);
+
+    let (_, stats) = send(client.get("/stats"));
+    assert_eq!(stats["games_played"], 2);
+    assert_eq!(stats["wins"], json!({ "alice": 1, "bob": 1 }));
+    assert_eq!(stats["average_turns"], 1.0);
+    assert_eq!(
+        stats["most_suggested"],
+        json!({ "suspect": "Scarlett", "weapon": "Rope", "room": "Kitchen" })
+    );
+}
+
+#[test]
+fn joins_stop_when_the_deck_runs_out_of_suspects() {
+    let client = client();
+    let alice = join(&client, "alice");
+    let (status, _) = send(
+        client
+            .put("/game/settings")
+            .header(bearer(&alice))
+            .json(&json!({ "deck": { "suspects": ["Plum", "Green", "Mustard"] } })),
+    );
+    assert_eq!(status, Status::Ok);
+    join(&client, "bob");
+    join(&client, "carol");
+
+    let (status, body) = send(client.post("/players/dave"));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "no characters remaining");
+}
+
+#[test]
+fn disproof_card_is_shown_to_the_suggester_only() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+
+    let card_seen = |request: LocalRequest<'_>| send(request).1[0]["outcome"]["card"].clone();
+    assert_eq!(
+        card_seen(client.get("/game/history").header(bearer(&alice))),
+        card("Mustard")
+    );
+    assert_eq!(
+        card_seen(client.get("/game/history").header(admin())),
+        card("Mustard")
+    );
+    assert_eq!(
+        card_seen(client.get("/game/history").header(bearer(&bob))),
+        Value::Null
+    );
+    assert_eq!(card_seen(client.get("/game/history")), Value::Null);
+}
+
+#[test]
+fn custom_secret_passage_can_be_taken() {
+    let client = client();
+    let [alice, bob, carol] = seated(
+        &client,
+        &THREE_HANDS,
+        json!({ "secret_passages": [["Kitchen", "Hall"]] }),
+    );
+
+    let (_, board) = send(client.get("/board"));
+    assert_eq!(board["secret_passages"], json!([["Kitchen", "Hall"]]));
+
+    move_to(&client, &alice, "Kitchen");
+    end_turn(&client, &alice);
+    end_turn(&client, &bob);
+    end_turn(&client, &carol);
+
+    let passage = |room: &str| {
+        send(
+            client
+                .post("/game/move")
+                .header(bearer(&alice))
+                .header(seq(&client))
+                .json(&json!({ "room": room, "secret_passage": true })),
+        )
+    };
+    let (status, body) = passage("Study");
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "no secret passage from here to that room");
+    assert_eq!(passage("Hall").0, Status::NoContent);
+}
+
+#[test]
+fn passage_to_the_same_room_is_refused() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .json(&json!({ "secret_passages": [["Kitchen", "Kitchen"]] })),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "invalid secret passages");
+    assert_eq!(
+        body["details"]["problems"],
+        json!(["passage from Kitchen leads back to itself"])
+    );
+}
+
+#[test]
+fn preview_deal_leaves_the_lobby_alone() {
+    let client = client();
+    join(&client, "alice");
+    join(&client, "bob");
+
+    let (status, preview) = send(client.post("/game/preview-deal").header(admin()));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(preview["hand_sizes"], json!([9, 9]));
+    assert_eq!(preview["fairness"]["even"], true);
+
+    let state = game(&client);
+    assert_eq!(state["phase"], "Lobby");
+    assert_eq!(state["solution"], Value::Null);
+    assert_eq!(state["players"][0]["cards"], json!([]));
+}
+
+#[test]
+fn conflicts_say_when_to_retry() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let response = client.post("/players/dave").dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(
+        response.into_json::<Value>().unwrap()["details"]["phase"],
+        "InProgress"
+    );
+
+    send(client.post("/game/pause").header(bearer(&alice)));
+    let response = client
+        .post("/game/end-turn")
+        .header(bearer(&alice))
+        .header(seq(&client))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(
+        response.headers().get_one("Retry-After"),
+        Some(PAUSED_RETRY_SECS.to_string().as_str())
+    );
+}
+
+#[test]
+fn passes_are_recorded_in_asking_order() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Scarlett", "Wrench", "Kitchen"]);
+
+    let (_, passes) = send(client.get("/game/passes/0"));
+    assert_eq!(
+        passes,
+        json!({ "passed": ["bob"], "disproved_by": "carol" })
+    );
+    assert_eq!(send(client.get("/game/passes/1")).0, Status::NotFound);
+}
+
+#[test]
+fn game_starts_itself_once_enough_players_join() {
+    let client = client();
+    let alice = join(&client, "alice");
+    send(
+        client
+            .put("/game/settings")
+            .header(bearer(&alice))
+            .json(&json!({ "auto_start_at": 3 })),
+    );
+
+    join(&client, "bob");
+    assert_eq!(game(&client)["phase"], "Lobby");
+    join(&client, "carol");
+
+    let state = game(&client);
+    assert_eq!(state["phase"], "InProgress");
+    assert_eq!(state["active_player"], "alice");
+}
+
+#[test]
+fn views_show_each_viewer_only_their_own() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let view = |request: LocalRequest<'_>| {
+        let state = send(request).1;
+        let cards: Vec<bool> = state["players"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|player| player.get("cards").is_some())
+            .collect();
+        (cards, state.get("solution").is_some())
+    };
+
+    assert_eq!(
+        view(client.get("/game").header(admin())),
+        (vec![true; 3], true)
+    );
+    assert_eq!(
+        view(client.get("/game").header(bearer(&alice))),
+        (vec![true, false, false], false)
+    );
+    assert_eq!(view(client.get("/game")), (vec![false; 3], false));
+    assert_eq!(send(client.get("/game")).1["players"][1]["hand_size"], 6);
+}
+
+#[test]
+fn turn_order_from_any_player() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (_, order) = send(client.get("/game/turn-order?from=bob"));
+    assert_eq!(order, json!(["bob", "carol", "alice"]));
+    let (_, order) = send(client.get("/game/turn-order?from=CAROL"));
+    assert_eq!(order, json!(["carol", "alice", "bob"]));
+    let (_, order) = send(client.get("/game/turn-order?from=ALICE"));
+    assert_eq!(order, json!(["alice", "bob", "carol"]));
+
+    let (status, body) = send(client.get("/game/turn-order?from=dave"));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(body["details"]["from"], "dave");
+}
+
+#[test]
+fn leaving_the_lobby_passes_the_host_role_on() {
+    let client = client();
+    let alice = join(&client, "alice");
+    let bob = join(&client, "bob");
+
+    let (status, _) = send(client.delete("/players/alice"));
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = send(client.delete("/players/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, _) = send(client.delete("/players/alice").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+    let state = game(&client);
+    assert_eq!(state["host"], "bob");
+    assert_eq!(state["players"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn leaving_mid_game_resigns() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, _) = send(client.delete("/players/alice").header(bearer(&alice)));
+    assert_eq!(status, Status::NoContent);
+
+    let state = game(&client);
+    assert_eq!(state["players"].as_array().unwrap().len(), 3);
+    assert_eq!(state["players"][0]["eliminated_reason"], "resigned");
+    assert_eq!(state["active_player"], "bob");
+}
+
+#[test]
+fn unseen_cards_count_the_starting_hint() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({}));
+
+    assert_eq!(unseen(&client, "alice", &alice), (12, 21));
+    let (status, _) = send(client.get("/game/unseen-count/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+
+    // The hint is in one of the two hands, so the other player sees it new.
+    let client = self::client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({ "starting_hint": true }));
+    let (alice, _) = unseen(&client, "alice", &alice);
+    let (bob, _) = unseen(&client, "bob", &bob);
+    assert_eq!(alice + bob, 12 + 11);
+}
+
+/// The unseen and total counts `name` gets.
+fn unseen(client: &Client, name: &str, token: &str) -> (u64, u64) {
+    let (_, body) = send(
+        client
+            .get(format!("/game/unseen-count/{name}"))
+            .header(bearer(token)),
+    );
+
+    (
+        body["unseen"].as_u64().unwrap(),
+        body["total"].as_u64().unwrap(),
+    )
+}
+
+#[test]
+fn summary_leaves_the_game_out() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (_, summary) = send(client.get("/game/summary"));
+    let keys: Vec<&String> = summary.as_object().unwrap().keys().collect();
+    assert_eq!(
+        keys,
+        [
+            "created_at",
+            "host",
+            "id",
+            "joinable",
+            "phase",
+            "players",
+            "started_at"
+        ]
+    );
+    assert_eq!(summary["players"], 3);
+    assert_eq!(summary["joinable"], false);
+    assert!(summary["created_at"].as_u64().unwrap() <= summary["started_at"].as_u64().unwrap());
+
+    // A cleared table is a new game, under a new id.
+    send(client.delete("/game").header(admin()));
+    let (_, cleared) = send(client.get("/game/summary"));
+    assert_ne!(cleared["id"], summary["id"]);
+    assert_eq!(cleared["phase"], "Lobby");
+}
+
+#[test]
+fn roll_sets_the_steps_left() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({ "dice_seed": 42 }));
+
+    let steps = || send(client.get("/game/remaining-steps/alice")).1["remaining_steps"].clone();
+    assert_eq!(steps(), 0);
+
+    let (_, rolled) = send(
+        client
+            .post("/game/roll")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    let roll = rolled["roll"].as_u64().unwrap();
+    assert!((2..=12).contains(&roll));
+    assert_eq!(steps(), roll);
+
+    let (status, body) = send(
+        client
+            .post("/game/roll")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "already rolled this turn");
+
+    move_to(&client, &alice, "Kitchen");
+    assert_eq!(steps(), 0);
+    assert_eq!(
+        send(
+            client
+                .post("/game/roll")
+                .header(bearer(&bob))
+                .header(seq(&client))
+        )
+        .0,
+        Status::Conflict
+    );
+}
+
+#[test]
+fn wrong_accusations_may_be_kept_private() {
+    let client = client();
+    let [alice, ..] = seated(
+        &client,
+        &THREE_HANDS,
+        json!({ "announce_wrong_accusation": false }),
+    );
+    accuse(&client, &alice, ["Plum", "Rope", "Hall"]);
+
+    let (_, public) = send(client.get("/game/eliminated"));
+    assert_eq!(
+        public,
+        json!([{ "name": "alice", "reason": "wrong_accusation" }])
+    );
+    let (_, admin_view) = send(client.get("/game/eliminated").header(admin()));
+    assert_eq!(admin_view[0]["accusation"]["suspect"], "Plum");
+}
+
+#[test]
+fn rematch_moves_the_first_seat_on() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(client.post("/game/rematch").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "game not finished");
+
+    accuse(&client, &alice, SOLUTION);
+    let (status, _) = send(client.post("/game/rematch").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+
+    let (status, created) = send(client.post("/game/rematch").header(bearer(&alice)));
+    assert_eq!(status, Status::Created);
+    assert_eq!(created["active_player"], "bob");
+    assert_eq!(created["winner"], Value::Null);
+    assert_eq!(
+        send(client.get("/game/turn-order?from=bob")).1,
+        json!(["bob", "carol", "alice"])
+    );
+}
+
+#[test]
+fn rematch_seats_only_those_still_playing() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+    accuse(&client, &alice, SOLUTION);
+    send(client.delete("/players/bob").header(bearer(&bob)));
+
+    let (status, created) = send(client.post("/game/rematch").header(bearer(&alice)));
+    assert_eq!(status, Status::Created, "{created}");
+    assert_eq!(created["active_player"], "carol");
+    assert_eq!(
+        send(client.get("/game/turn-order?from=carol")).1,
+        json!(["carol", "alice"])
+    );
+}
+
+#[test]
+fn refused_rematch_keeps_the_finished_game() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({}));
+    accuse(&client, &alice, SOLUTION);
+    send(client.delete("/players/bob").header(bearer(&bob)));
+    let finished = game(&client);
+
+    let (status, body) = send(client.post("/game/rematch").header(bearer(&alice)));
+    assert_eq!(status, Status::Conflict, "{body}");
+
+    let state = game(&client);
+    assert_eq!(state["phase"], "Finished");
+    assert_eq!(state["winner"], "alice");
+    assert_eq!(state["solution"], finished["solution"]);
+    assert_eq!(state["players"], finished["players"]);
+}
+
+#[test]
+fn cards_by_index() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+
+    let (_, history) = send(
+        client
+            .get("/game/history?repr=index")
+            .header(bearer(&alice)),
+    );
+    assert_eq!(history[0]["suspect"], 2);
+    assert_eq!(history[0]["weapon"], 3);
+    assert_eq!(history[0]["room"], 0);
+    assert_eq!(history[0]["outcome"]["card"], json!({ "Suspect": 2 }));
+
+    let (_, cards) = send(client.get("/cards?repr=index"));
+    assert_eq!(cards["cards"][0], json!({ "Suspect": 0 }));
+    assert_eq!(cards["rooms"][0], "Kitchen");
+}
+
+#[test]
+fn validate_config_warns_about_uneven_hands() {
+    let client = client();
+    for name in ["alice", "bob", "carol", "dave"] {
+        join(&client, name);
+    }
+
+    let (_, report) = send(client.post("/game/validate-config").json(&json!({})));
+    assert_eq!(report["valid"], true);
+    assert_eq!(
+        report["warnings"],
+        json!(["18 cards don't divide among 4 players: hands of 4 and 5"])
+    );
+    assert_eq!(game(&client)["phase"], "Lobby");
+}
+
+#[test]
+fn validate_config_reports_unknown_settings() {
+    let client = client();
+    join(&client, "alice");
+    join(&client, "bob");
+
+    let (_, report) = send(
+        client
+            .post("/game/validate-config")
+            .json(&json!({ "open_infoo": true })),
+    );
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["errors"][0]["error"], "invalid settings");
+    let reason = report["errors"][0]["details"]["reason"].as_str().unwrap();
+    assert!(reason.contains("open_infoo"), "{reason}");
+
+    let (_, report) = send(
+        client
+            .post("/game/validate-config")
+            .json(&json!({ "theme": "neon", "auto_start_at": 9 })),
+    );
+    let errors: Vec<&Value> = report["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| &error["error"])
+        .collect();
+    assert_eq!(errors, ["unknown theme", "invalid auto_start_at"]);
+}
+
+#[test]
+fn intrigue_cards_are_dealt_and_played() {
+    let client = client();
+    let [alice, bob] = seated(
+        &client,
+        &TWO_HANDS,
+        json!({ "deck": { "intrigue": ["SuggestAnywhere", "SeeACard"] } }),
+    );
+
+    let held = |name: &str, token: &str| {
+        send(client.get(format!("/players/{name}")).header(bearer(token))).1["intrigue_cards"]
+            .as_array()
+            .unwrap()
+            .len()
+    };
+    assert_eq!((held("alice", &alice), held("bob", &bob)), (1, 1));
+    let card =
+        send(client.get("/players/alice").header(bearer(&alice))).1["intrigue_cards"][0].clone();
+
+    let play = |card: &Value| {
+        send(
+            client
+                .post("/game/play-intrigue")
+                .header(bearer(&alice))
+                .header(seq(&client))
+                .json(&json!({ "card": card })),
+        )
+    };
+    assert_eq!(play(&card).0, Status::NoContent);
+    assert_eq!(
+        game(&client)["intrigue_played"],
+        json!([{ "player": "alice", "turn": 1, "card": card }])
+    );
+
+    let (status, body) = play(&card);
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "intrigue card not held");
+}
+
+#[test]
+fn suggest_anywhere_frees_the_suggestion_from_the_room() {
+    let client = client();
+    let [alice, _] = seated(
+        &client,
+        &TWO_HANDS,
+        json!({ "deck": { "intrigue": ["SuggestAnywhere"] } }),
+    );
+
+    let (status, body) = suggest(&client, &alice, ["Orchid", "Rope", "Library"]);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "not in the suggested room");
+
+    send(
+        client
+            .post("/game/play-intrigue")
+            .header(bearer(&alice))
+            .header(seq(&client))
+            .json(&json!({ "card": "SuggestAnywhere" })),
+    );
+    assert_eq!(
+        suggest(&client, &alice, ["Orchid", "Rope", "Library"]).0,
+        Status::Ok
+    );
+}
+
+#[test]
+fn visited_rooms_are_listed_once() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({}));
+
+    for room in ["Kitchen", "Hall", "Kitchen"] {
+        move_to(&client, &alice, room);
+        end_turn(&client, &alice);
+        end_turn(&client, &bob);
+    }
+
+    let (_, rooms) = send(client.get("/game/visited-rooms/alice"));
+    assert_eq!(rooms, json!(["Kitchen", "Hall"]));
+    assert_eq!(send(client.get("/game/visited-rooms/bob")).1, json!([]));
+}
+
+#[test]
+fn suggestions_may_need_a_move_first() {
+    let client = client();
+    let [alice, bob] = seated(
+        &client,
+        &TWO_HANDS,
+        json!({ "require_move_before_suggest": true }),
+    );
+
+    move_to(&client, &alice, "Kitchen");
+    let (status, body) = send(
+        client
+            .post("/game/end-turn")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "suggest or declare no suggestion first");
+    send(
+        client
+            .post("/game/no-suggestion")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    end_turn(&client, &alice);
+    end_turn(&client, &bob);
+
+    // Still in the Kitchen, but not moved into it this turn.
+    let (status, body) = suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "must move into the suggested room first");
+}
+
+#[test]
+fn admin_forces_the_next_turn() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, _) = send(client.post("/game/force-next-turn"));
+    assert_eq!(status, Status::Unauthorized);
+
+    let (status, forced) = send(client.post("/game/force-next-turn").header(admin()));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(forced, json!({ "turn_number": 2, "active_player": "bob" }));
+
+    let (_, audit) = send(client.get("/admin/audit").header(admin()));
+    let forced = audit
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["action"] == "forced next turn")
+        .unwrap();
+    assert_eq!(forced["detail"], "turn 1: skipped alice, now bob");
+}
+
+#[test]
+fn whole_turn_in_one_request() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, changes) = send(
+        client
+            .post("/game/turn")
+            .header(bearer(&alice))
+            .header(seq(&client))
+            .json(&json!({
+                "move": { "room": "Kitchen" },
+                "suggest": { "suspect": "Mustard", "weapon": "Rope", "room": "Kitchen" },
+                "end_turn": true,
+            })),
+    );
+    assert_eq!(status, Status::Ok);
+    assert_eq!(changes["moved_to"], "Kitchen");
+    assert_eq!(changes["suggestion"]["outcome"]["by"], "bob");
+    assert_eq!(changes["turn_number"], 2);
+    // Once for the turn played, once for passing it on.
+    assert_eq!(changes["action_seq"], 2);
+    assert_eq!(game(&client)["active_player"], "bob");
+}
+
+#[test]
+fn refused_turn_is_rolled_back() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(
+        client
+            .post("/game/turn")
+            .header(bearer(&alice))
+            .header(seq(&client))
+            .json(&json!({
+                "move": { "room": "Kitchen" },
+                "suggest": { "suspect": "Mustard", "weapon": "Rope", "room": "Hall" },
+            })),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "turn refused");
+    assert_eq!(body["details"]["step"], "suggest");
+    assert_eq!(
+        body["details"]["cause"]["error"],
+        "not in the suggested room"
+    );
+
+    let state = game(&client);
+    assert_eq!(state["players"][0]["location"], "PlumStart");
+    assert_eq!(state["action_seq"], 0);
+    assert_eq!(send(client.get("/game/moves/alice")).1, json!([]));
+}
+
+#[test]
+fn first_disproof_may_be_shown_to_everyone() {
+    let client = client();
+    let [alice, bob, _] = seated(
+        &client,
+        &THREE_HANDS,
+        json!({ "open_first_disproof": true }),
+    );
+
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+    end_turn(&client, &alice);
+    move_to(&client, &bob, "Lounge");
+    suggest(&client, &bob, ["Scarlett", "Wrench", "Lounge"]);
+
+    let (_, history) = send(client.get("/game/history"));
+    assert_eq!(history[0]["outcome"]["card"], card("Mustard"));
+    assert_eq!(history[1]["outcome"]["card"], Value::Null);
+}
+
+#[test]
+fn simulated_deals_repeat_for_a_seed() {
+    let client = client();
+
+    let (_, deal) = send(client.get("/simulate/deal?players=3&seed=5"));
+    assert_eq!(send(client.get("/simulate/deal?players=3&seed=5")).1, deal);
+    assert_ne!(send(client.get("/simulate/deal?players=3&seed=6")).1, deal);
+    assert_eq!(deal["solution_seed"], 5);
+    let hand_sizes: Vec<usize> = deal["hands"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|hand| hand.as_array().unwrap().len())
+        .collect();
+    assert_eq!(hand_sizes, [6, 6, 6]);
+
+    let (status, body) = send(client.get("/simulate/deal?players=7"));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["details"], json!({ "min": 2, "max": 6, "given": 7 }));
+}
+
+#[test]
+fn simulated_deal_labels_every_card() {
+    let client = client();
+
+    let (_, deal) = send(client.get("/simulate/deal?players=2&seed=5&labeled=true"));
+    let labels = deal["cards"].as_array().unwrap();
+    assert_eq!(labels.len(), 21);
+
+    let holders = |holder: &str| {
+        labels
+            .iter()
+            .filter(|label| label["holder"] == holder)
+            .count()
+    };
+    assert_eq!(holders("solution"), 3);
+    assert_eq!(holders("player 1"), 9);
+    assert_eq!(holders("player 2"), 9);
+    for label in labels.iter().filter(|label| label["holder"] == "player 1") {
+        assert!(
+            deal["hands"][0]
+                .as_array()
+                .unwrap()
+                .contains(&label["card"])
+        );
+    }
+}
+
+#[test]
+fn card_order_is_declaration_order() {
+    let client = client();
+
+    let (_, order) = send(client.get("/cards/order"));
+    assert_eq!(order["suspects"][0], json!({ "index": 0, "name": "Plum" }));
+    assert_eq!(order["weapons"][5], json!({ "index": 5, "name": "Wrench" }));
+    assert_eq!(order["rooms"][8], json!({ "index": 8, "name": "Study" }));
+}
+
+#[test]
+fn notebook_exports_as_csv() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    send(
+        client
+            .post("/game/notebook/alice/mark")
+            .header(bearer(&alice))
+            .json(&json!({ "card": card("Rope"), "mark": { "held_by": "carol" } })),
+    );
+
+    let response = client
+        .get("/game/notebook/alice/export?format=csv")
+        .header(bearer(&alice))
+        .dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    let csv = response.into_string().unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(rows.len(), 22);
+    assert_eq!(rows[0], "card,category,mark,held_by,seen");
+    assert_eq!(rows[1], "Plum,suspect,,,true");
+    assert_eq!(rows[10], "Rope,weapon,held_by,carol,false");
+
+    let (_, json_rows) = send(
+        client
+            .get("/game/notebook/alice/export")
+            .header(bearer(&alice)),
+    );
+    assert_eq!(
+        json_rows[9],
+        json!({ "card": "Rope", "category": "weapon", "mark": "held_by", "held_by": "carol", "seen": false })
+    );
+}
+
+#[test]
+fn suggester_may_learn_which_cards_nobody_holds() {
+    for analytics in [true, false] {
+        let client = client();
+        let [alice, ..] = seated(
+            &client,
+            &THREE_HANDS,
+            json!({ "suggestion_analytics": analytics }),
+        );
+
+        move_to(&client, &alice, "Kitchen");
+        let (_, result) = suggest(&client, &alice, ["Orchid", "Wrench", "Kitchen"]);
+        if analytics {
+            assert_eq!(result["unheld"], cards(&["Orchid", "Wrench"]));
+        } else {
+            assert!(result.get("unheld").is_none());
+        }
+    }
+}
+
+#[test]
+fn config_hides_the_seeds_until_the_game_is_over() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({ "turn_timer_secs": 90 }));
+
+    let (_, config) = send(client.get("/game/config"));
+    assert_eq!(config["turn_timer_secs"], 90);
+    assert_eq!(config["solution_seed"], Value::Null);
+    assert_eq!(config["deal_seed"], Value::Null);
+    let (_, config) = send(client.get("/game/config").header(admin()));
+    assert!(config["solution_seed"].is_u64());
+
+    accuse(&client, &alice, SOLUTION);
+    let (_, config) = send(client.get("/game/config"));
+    assert!(config["solution_seed"].is_u64());
+    assert!(config["deal_seed"].is_u64());
+}
+
+#[test]
+fn reveal_explains_what_is_wrong_with_the_card() {
+    let client = client();
+    let [alice, bob, carol] = seated(&client, &THREE_HANDS, json!({ "disproof_mode": "choose" }));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Dagger", "Kitchen"]);
+
+    let reveal = |token: &str, shown: &str| {
+        send(
+            client
+                .post("/game/reveal")
+                .header(bearer(token))
+                .json(&json!({ "card": card(shown) })),
+        )
+    };
+
+    let (status, body) = reveal(&bob, "Rope");
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "card not in your hand");
+
+    let (status, body) = reveal(&bob, "Lounge");
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "card does not disprove the suggestion");
+    assert_eq!(
+        body["details"]["suggested"],
+        cards(&["Mustard", "Dagger", "Kitchen"])
+    );
+
+    let (status, body) = reveal(&carol, "Rope");
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "no disproof awaiting you");
+}
+
+#[test]
+fn pending_disproofs_are_listed() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({ "disproof_mode": "choose" }));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Dagger", "Kitchen"]);
+
+    let (_, pending) = send(client.get("/game/pending-disproofs"));
+    assert_eq!(
+        pending,
+        json!([{ "index": 0, "suggested_by": "alice", "awaiting": "bob" }])
+    );
+
+    send(
+        client
+            .post("/game/reveal")
+            .header(bearer(&bob))
+            .json(&json!({ "card": card("Mustard") })),
+    );
+    assert_eq!(send(client.get("/game/pending-disproofs")).1, json!([]));
+}
+
+#[test]
+fn slow_disprover_has_their_lowest_card_shown() {
+    let client = client();
+    let [alice, ..] = seated(
+        &client,
+        &THREE_HANDS,
+        json!({
+            "disproof_mode": "choose",
+            "disproof_timeout_secs": 30,
+            "disproof_timeout_pick": "lowest",
+        }),
+    );
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Scarlett", "Rope", "Kitchen"]);
+
+    with_game(&client, |state| state.disproof_started_at -= 30);
+
+    let (_, history) = send(client.get("/game/history").header(bearer(&alice)));
+    assert_eq!(
+        history[0]["outcome"],
+        json!({ "status": "disproved", "by": "carol", "shown_to": "alice", "card": card("Scarlett") })
+    );
+    assert!(audit_actions(&client).contains(&"disproof timed out".to_owned()));
+}
+
+#[test]
+fn rooms_outside_the_deck_are_refused() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    start(&client, &alice, json!({ "preset": "quick" }));
+
+    let (status, body) = send(
+        client
+            .post("/game/move")
+            .header(bearer(&alice))
+            .header(seq(&client))
+            .json(&json!({ "room": "Hall" })),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "card not in play");
+    assert_eq!(body["details"]["card"], card("Hall"));
+
+    move_to(&client, &alice, "Kitchen");
+    let (status, body) = suggest(&client, &alice, ["Plum", "Rope", "Hall"]);
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["details"]["card"], card("Hall"));
+    let (status, _) = accuse(&client, &alice, ["Plum", "Rope", "Hall"]);
+    assert_eq!(status, Status::UnprocessableEntity);
+}
+
+#[test]
+fn winner_by_accusation() {
+    let client = client();
+    let [alice, _] = seated(&client, &TWO_HANDS, json!({}));
+
+    let (status, body) = send(client.get("/game/winner"));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(body["details"]["phase"], "InProgress");
+
+    let (_, result) = accuse(&client, &alice, SOLUTION);
+    assert_eq!(result["correct"], true);
+
+    let (_, winner) = send(client.get("/game/winner"));
+    assert_eq!(winner["winner"], "alice");
+    assert_eq!(winner["won_by"], "accusation");
+    assert_eq!(winner["accusation"]["room"], "Study");
+}
+
+#[test]
+fn winner_as_last_player_standing() {
+    let client = client();
+    let [alice, _] = seated(&client, &TWO_HANDS, json!({}));
+
+    let (_, result) = accuse(&client, &alice, ["Plum", "Rope", "Hall"]);
+    assert_eq!(result["correct"], false);
+
+    let (_, winner) = send(client.get("/game/winner"));
+    assert_eq!(
+        winner,
+        json!({
+            "winner": "bob",
+            "won_by": "last_player_standing",
+            "accusation": null,
+            "timed_out": false,
+        })
+    );
+}
+
+#[test]
+fn cut_deck_deals_reproducibly() {
+    let deal = |cut: bool| {
+        let client = client();
+        let alice = join(&client, "alice");
+        join(&client, "bob");
+        let settings = json!({ "solution_seed": 3, "deal_seed": 4, "cut_deck": cut });
+        send(
+            client
+                .post("/game")
+                .header(bearer(&alice))
+                .header(admin())
+                .json(&settings),
+        );
+        game(&client)["players"][0]["cards"].clone()
+    };
+
+    assert_eq!(deal(true), deal(true));
+    assert_ne!(deal(true), deal(false));
+}
+
+#[test]
+fn rules_list_the_variants_in_effect() {
+    let client = client();
+    let alice = join(&client, "alice");
+
+    let settings = |rules: &Value| -> Vec<String> {
+        rules
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|rule| rule["setting"].as_str().unwrap().to_owned())
+            .collect()
+    };
+    let (_, rules) = send(client.get("/game/rules"));
+    assert_eq!(
+        settings(&rules),
+        [
+            "deck",
+            "disproof_direction",
+            "disproof_mode",
+            "secret_passages"
+        ]
+    );
+    assert_eq!(rules[0]["summary"], "6 suspects, 6 weapons and 9 rooms");
+
+    send(
+        client
+            .put("/game/settings")
+            .header(bearer(&alice))
+            .json(&json!({ "turn_timer_secs": 45, "forbid_self_suspect": true })),
+    );
+    let (_, rules) = send(client.get("/game/rules"));
+    assert_eq!(
+        settings(&rules)[4..],
+        ["turn_timer_secs", "forbid_self_suspect"]
+    );
+    assert_eq!(rules[4]["summary"], "a turn passes on after 45 seconds");
+}
+
+#[test]
+fn suggestions_filtered_by_room() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+    end_turn(&client, &alice);
+    move_to(&client, &bob, "Lounge");
+    suggest(&client, &bob, ["Scarlett", "Wrench", "Lounge"]);
+
+    let (_, in_kitchen) = send(client.get("/game/suggestions-in/Kitchen"));
+    assert_eq!(in_kitchen.as_array().unwrap().len(), 1);
+    assert_eq!(in_kitchen[0]["suggested_by"], "alice");
+    assert_eq!(send(client.get("/game/suggestions-in/Study")).1, json!([]));
+
+    let (status, body) = send(client.get("/game/suggestions-in/Attic"));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(body["error"], "unknown room");
+}
+
+#[test]
+fn stale_action_sequence_is_refused() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let with_seq = |seq: &str| {
+        send(
+            client
+                .post("/game/move")
+                .header(bearer(&alice))
+                .header(Header::new("X-Action-Seq", seq.to_owned()))
+                .json(&json!({ "room": "Kitchen" })),
+        )
+    };
+
+    // Two clients sending the same move: only the first goes through.
+    assert_eq!(with_seq("0").0, Status::NoContent);
+    let (status, body) = with_seq("0");
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "stale action sequence");
+    assert_eq!(body["details"], json!({ "expected": 1, "given": 0 }));
+    assert_eq!(
+        send(client.get("/game/moves/alice"))
+            .1
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let (status, body) = with_seq("one");
+    assert_eq!(status, Status::BadRequest);
+    assert_eq!(body["error"], "X-Action-Seq is not a number");
+
+    let (status, body) = send(client.post("/game/roll").header(bearer(&alice)));
+    assert_eq!(status, Status::PreconditionRequired);
+    assert_eq!(body["error"], "missing X-Action-Seq");
+}
+
+#[test]
+fn turns_passing_on_bump_the_action_sequence() {
+    let client = client();
+    let [alice, _, carol] = seated(&client, &THREE_HANDS, json!({ "turn_timer_secs": 60 }));
+    let current = || game(&client)["action_seq"].as_u64().unwrap();
+    let queued = current();
+
+    // Alice's turn times out, bob's is forced on and carol resigns.
+    with_game(&client, |state| state.turn_started_at -= 120);
+    assert_eq!(game(&client)["active_player"], "bob");
+    assert!(current() > queued);
+
+    let before = current();
+    send(client.post("/game/force-next-turn").header(admin()));
+    assert_eq!(game(&client)["active_player"], "carol");
+    assert!(current() > before);
+
+    let before = current();
+    let (status, _) = send(client.post("/game/resign/carol").header(bearer(&carol)));
+    assert_eq!(status, Status::NoContent);
+    assert_eq!(game(&client)["active_player"], "alice");
+    assert!(current() > before);
+
+    // A move queued before all that is refused.
+    let (status, _) = send(
+        client
+            .post("/game/move")
+            .header(bearer(&alice))
+            .header(Header::new("X-Action-Seq", queued.to_string()))
+            .json(&json!({ "room": "Kitchen" })),
+    );
+    assert_eq!(status, Status::Conflict);
+}
+
+#[test]
+fn one_of_two_concurrent_suggestions_goes_through() {
+    use rocket::{local::asynchronous, tokio};
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .unwrap();
+
+    runtime.block_on(async {
+        let client = Arc::new(
+            asynchronous::Client::tracked(server_with(json!({})))
+                .await
+                .unwrap(),
+        );
+        let mut tokens = Vec::new();
+        for name in ["alice", "bob", "carol"] {
+            let response = client.post(format!("/players/{name}")).dispatch().await;
+            let body: Value = response.into_json().await.unwrap();
+            tokens.push(body["token"].as_str().unwrap().to_owned());
+        }
+        let alice = tokens[0].clone();
+        let started = client.post("/game").header(bearer(&alice)).dispatch().await;
+        assert_eq!(started.status(), Status::Created);
+        let moved = client
+            .post("/game/move")
+            .header(bearer(&alice))
+            .header(Header::new("X-Action-Seq", "0"))
+            .json(&json!({ "room": "Kitchen" }))
+            .dispatch()
+            .await;
+        assert_eq!(moved.status(), Status::NoContent);
+
+        // Both sent with the sequence number they saw after the move.
+        let attempts = (0..2).map(|_| {
+            let client = Arc::clone(&client);
+            let alice = alice.clone();
+            tokio::spawn(async move {
+                let response = client
+                    .post("/suggest")
+                    .header(bearer(&alice))
+                    .header(Header::new("X-Action-Seq", "1"))
+                    .json(&json!({ "suspect": "Mustard", "weapon": "Rope", "room": "Kitchen" }))
+                    .dispatch()
+                    .await;
+                let status = response.status();
+                (status, response.into_json::<Value>().await.unwrap())
+            })
+        });
+        let mut outcomes = Vec::new();
+        for attempt in attempts.collect::<Vec<_>>() {
+            outcomes.push(attempt.await.unwrap());
+        }
+
+        let accepted = outcomes.iter().filter(|(status, _)| *status == Status::Ok);
+        assert_eq!(accepted.count(), 1, "{outcomes:?}");
+        let refused: Vec<&Value> = outcomes
+            .iter()
+            .filter(|(status, _)| *status == Status::Conflict)
+            .map(|(_, body)| body)
+            .collect();
+        assert_eq!(refused.len(), 1, "{outcomes:?}");
+        assert_eq!(refused[0]["error"], "stale action sequence");
+
+        let history = client.get("/game/history").dispatch().await;
+        let history: Value = history.into_json().await.unwrap();
+        assert_eq!(history.as_array().unwrap().len(), 1);
+    });
+}
+
+#[test]
+fn solved_once_every_other_card_is_placed() {
+    let client = client();
+    let [alice, _] = seated(&client, &TWO_HANDS, json!({}));
+
+    let solved = || send(client.get("/game/solved/alice").header(bearer(&alice))).1;
+    assert_eq!(
+        solved(),
+        json!({ "solved": false, "suspect": null, "weapon": null, "room": null })
+    );
+
+    for shown in TWO_HANDS[1].1 {
+        send(
+            client
+                .post("/game/notebook/alice/mark")
+                .header(bearer(&alice))
+                .json(&json!({ "card": card(shown), "mark": { "held_by": "bob" } })),
+        );
+    }
+    assert_eq!(
+        solved(),
+        json!({
+            "solved": true,
+            "suspect": card("Orchid"),
+            "weapon": card("Wrench"),
+            "room": card("Study"),
+        })
+    );
+}
+
+#[test]
+fn seating_follows_the_seating_seed() {
+    let seating = |seed: u64| {
+        let client = client();
+        let alice = join(&client, "alice");
+        for name in ["bob", "carol", "dave", "erin"] {
+            join(&client, name);
+        }
+        let settings = json!({ "randomize_turn_order": true, "seating_seed": seed });
+        start(&client, &alice, settings);
+        send(client.get("/game/turn-order?from=alice")).1
+    };
+
+    let order = seating(11);
+    assert_eq!(seating(11), order);
+    let mut seated: Vec<&str> = order
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|name| name.as_str().unwrap())
+        .collect();
+    seated.sort_unstable();
+    assert_eq!(seated, ["alice", "bob", "carol", "dave", "erin"]);
+    assert!((12..20).any(|seed| seating(seed) != order));
+}
+
+#[test]
+fn passing_on_a_suggestion_is_a_known_absence() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Scarlett", "Wrench", "Kitchen"]);
+
+    let (_, absent) = send(client.get("/game/known-absences/bob"));
+    assert_eq!(absent, cards(&["Scarlett", "Wrench", "Kitchen"]));
+    assert_eq!(send(client.get("/game/known-absences/carol")).1, json!([]));
+}
+
+#[test]
+fn daily_puzzle_is_the_same_all_day() {
+    let dealer = RandomDealer;
+    let puzzle = |day| {
+        let puzzle = daily_puzzle(day, &dealer).unwrap();
+        (
+            to_value(&puzzle.solution).unwrap(),
+            to_value(&puzzle.players).unwrap(),
+        )
+    };
+
+    assert_eq!(puzzle(20_000), puzzle(20_000));
+    assert_ne!(puzzle(20_000), puzzle(20_001));
+}
+
+#[test]
+fn daily_puzzle_by_date() {
+    let client = client();
+
+    let (_, daily) = send(client.get("/daily?date=2026-01-01"));
+    assert_eq!(daily["date"], "2026-01-01");
+    assert_eq!(daily["hand"].as_array().unwrap().len(), 5);
+    assert_eq!(
+        daily["opponents"].as_array().unwrap().len(),
+        DAILY_OPPONENTS
+    );
+    assert_eq!(send(client.get("/daily?date=2026-01-01")).1, daily);
+
+    let (status, body) = send(client.get("/daily?date=2026-02-30"));
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(body["error"], "invalid date");
+}
+
+#[test]
+fn suggestion_must_name_the_room_the_suggester_is_in() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Hall");
+
+    let (status, body) = suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "not in the suggested room");
+    assert_eq!(
+        body["details"],
+        json!({ "room": "Kitchen", "location": "Hall" })
+    );
+}
+
+#[test]
+fn starting_hint_is_shown_to_everyone() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({ "starting_hint": true }));
+
+    let state = game(&client);
+    let hint = &state["starting_hint"];
+    assert!(!cards(&SOLUTION).as_array().unwrap().contains(hint));
+    assert_eq!(send(client.get("/game")).1["starting_hint"], *hint);
+
+    for player in state["players"].as_array().unwrap() {
+        let noted = player["notebook"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["card"] == *hint)
+            .unwrap();
+        if player["cards"].as_array().unwrap().contains(hint) {
+            assert_eq!(noted["mark"], "have");
+        } else {
+            assert!(noted["mark"]["held_by"].is_string());
+        }
+    }
+}
+
+#[test]
+fn events_since_a_sequence_number() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (_, events) = send(client.get("/game/events/since/0"));
+    let kinds: Vec<&Value> = events
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| &e["kind"])
+        .collect();
+    assert_eq!(kinds, ["join", "join", "join", "start"]);
+
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+
+    let (_, events) = send(client.get("/game/events/since/4").header(bearer(&bob)));
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["seq"], 5);
+    assert_eq!(events[0]["kind"], "suggest");
+    assert_eq!(events[0]["record"]["outcome"]["by"], "bob");
+    assert_eq!(events[0]["record"]["outcome"]["card"], Value::Null);
+
+    let (_, events) = send(client.get("/game/events/since/4").header(bearer(&alice)));
+    assert_eq!(events[0]["record"]["outcome"]["card"], card("Mustard"));
+}
+
+#[test]
+fn last_player_leaving_abandons_the_game() {
+    let client = client();
+    let [alice, bob] = seated(&client, &TWO_HANDS, json!({}));
+    accuse(&client, &alice, SOLUTION);
+
+    send(client.delete("/players/alice").header(bearer(&alice)));
+    assert_eq!(game(&client)["phase"], "Finished");
+    send(client.delete("/players/bob").header(bearer(&bob)));
+
+    let state = game(&client);
+    assert_eq!(state["phase"], "Lobby");
+    assert_eq!(state["players"], json!([]));
+    let audit = client.rocket().state::<AuditLog>().unwrap().entries();
+    let abandoned = audit.iter().find(|entry| entry.action == "game abandoned");
+    assert_eq!(abandoned.unwrap().detail, "bob left the Finished game last");
+}
+
+#[test]
+fn admins_may_see_the_solution_as_they_delete_the_game() {
+    let client = client_with(json!({ "reveal_on_delete": true }));
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(client.delete("/game?reveal=true").header(bearer(&alice)));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(body["error"], "only admins may reveal the solution");
+
+    let (status, body) = send(client.delete("/game?reveal=true").header(admin()));
+    assert_eq!(status, Status::Ok);
+    assert_eq!(
+        body["solution"],
+        json!({ "suspect": "Orchid", "weapon": "Wrench", "room": "Study" })
+    );
+    assert_eq!(game(&client)["phase"], "Lobby");
+}
+
+#[test]
+fn revealing_on_delete_must_be_enabled() {
+    let client = client();
+    seated(&client, &THREE_HANDS, json!({}));
+
+    let (status, body) = send(client.delete("/game?reveal=true").header(admin()));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(body["error"], "reveal_on_delete is not enabled");
+    assert_eq!(game(&client)["phase"], "InProgress");
+}
+
+#[test]
+fn distance_between_rooms() {
+    let client = client();
+    let alice = join(&client, "alice");
+
+    let steps = |from: &str, to: &str| {
+        send(client.get(format!("/board/distance?from={from}&to={to}"))).1["steps"].clone()
+    };
+    assert_eq!(steps("Kitchen", "Ballroom"), 7);
+    assert_eq!(steps("Ballroom", "Kitchen"), 7);
+    assert_eq!(steps("Kitchen", "Study"), 0);
+
+    send(
+        client
+            .put("/game/settings")
+            .header(bearer(&alice))
+            .json(&json!({ "passage_steps": 3 })),
+    );
+    assert_eq!(steps("Kitchen", "Study"), 3);
+
+    let (status, body) = send(client.get("/board/distance?from=Kitchen&to=Attic"));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(body["details"]["room"], "Attic");
+}
+
+#[test]
+fn fixed_hands_must_partition_the_deck() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let mut hands = json!({
+        "alice": cards(TWO_HANDS[0].1),
+        "bob": cards(TWO_HANDS[1].1),
+    });
+    hands["alice"].as_array_mut().unwrap().push(card("Orchid"));
+    let body = json!({ "hands": hands });
+
+    let (status, error) = send(client.post("/game").header(bearer(&alice)).json(&body));
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(error["error"], "only admins may fix the hands");
+
+    let (status, error) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .header(admin())
+            .json(&body),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(error["error"], "hands don't partition the deck");
+    assert_eq!(
+        error["details"]["problems"],
+        json!(["left for the envelope: Wrench, Study; expected one suspect, weapon and room"])
+    );
+    assert_eq!(game(&client)["phase"], "Lobby");
+}
+
+#[test]
+fn winnable_until_eliminated() {
+    let client = client();
+    let alice = join(&client, "alice");
+    let winnable = || send(client.get("/game/winnable/alice").header(bearer(&alice))).1;
+    assert_eq!(winnable()["reason"], "game not in progress");
+
+    let client = self::client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+    let winnable = || send(client.get("/game/winnable/alice").header(bearer(&alice))).1;
+    assert_eq!(winnable(), json!({ "winnable": true, "reason": null }));
+
+    accuse(&client, &alice, ["Plum", "Rope", "Hall"]);
+    assert_eq!(
+        winnable(),
+        json!({ "winnable": false, "reason": "eliminated" })
+    );
+    let (status, _) = send(client.get("/game/winnable/alice").header(bearer(&bob)));
+    assert_eq!(status, Status::Forbidden);
+}
+
+#[test]
+fn hand_sorted_by_category() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    with_game(&client, |state| state.players[0].cards.reverse());
+
+    let hand = |query: &str| {
+        send(
+            client
+                .get(format!("/players/alice{query}"))
+                .header(bearer(&alice)),
+        )
+        .1["cards"]
+            .clone()
+    };
+    let mut dealt: Vec<&str> = THREE_HANDS[0].1.to_vec();
+    assert_eq!(hand("?sort=category"), cards(&dealt));
+    dealt.reverse();
+    assert_eq!(hand(""), cards(&dealt));
+}
+
+#[test]
+fn games_set_up_from_a_registered_edition() {
+    let client = client();
+    let edition = json!({
+        "name": "mini",
+        "deck": { "suspects": ["Plum", "Green"], "weapons": ["Rope"], "rooms": ["Hall", "Study"] },
+        "secret_passages": [],
+    });
+
+    let (status, _) = send(client.post("/editions").json(&edition));
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = send(client.post("/editions").header(admin()).json(&edition));
+    assert_eq!(status, Status::Created);
+    let (status, body) = send(client.post("/editions").header(admin()).json(&edition));
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "edition already registered");
+    assert!(send(client.get("/editions")).1.get("mini").is_some());
+
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    start(&client, &alice, json!({ "edition": "mini" }));
+    let (_, config) = send(client.get("/game/config"));
+    assert_eq!(config["deck"]["rooms"], json!(["Hall", "Study"]));
+    assert_eq!(config["secret_passages"], json!([]));
+}
+
+#[test]
+fn unknown_edition_is_refused() {
+    let client = client();
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+
+    let (status, body) = send(
+        client
+            .post("/game")
+            .header(bearer(&alice))
+            .json(&json!({ "edition": "deluxe" })),
+    );
+    assert_eq!(status, Status::UnprocessableEntity);
+    assert_eq!(
+        body["details"],
+        json!({ "edition": "deluxe", "allowed": [] })
+    );
+}
+
+#[test]
+fn unmatched_routes_answer_in_json() {
+    let client = client();
+
+    let (status, body) = send(client.get("/nowhere"));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(
+        body,
+        json!({ "error": "Not Found", "details": { "path": "/nowhere" } })
+    );
+
+    let (status, body) = send(client.get("/admin/audit"));
+    assert_eq!(status, Status::Unauthorized);
+    assert_eq!(body["error"], "Unauthorized");
+}
+
+#[test]
+fn declining_to_suggest() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+    move_to(&client, &alice, "Kitchen");
+
+    let (status, _) = send(
+        client
+            .post("/game/no-suggestion")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::NoContent);
+    let (status, body) = send(
+        client
+            .post("/game/no-suggestion")
+            .header(bearer(&alice))
+            .header(seq(&client)),
+    );
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "already decided on a suggestion this turn");
+
+    let (status, body) = suggest(&client, &alice, ["Mustard", "Rope", "Kitchen"]);
+    assert_eq!(status, Status::Conflict);
+    assert_eq!(body["error"], "declared no suggestion this turn");
+
+    let (_, events) = send(client.get("/game/events/since/4"));
+    assert_eq!(
+        events,
+        json!([{ "seq": 5, "kind": "no_suggestion", "player": "alice", "turn": 1 }])
+    );
+
+    let (_, history) = send(client.get("/game/history"));
+    assert_eq!(history, json!([{ "no_suggestion_by": "alice", "turn": 1 }]));
+    end_turn(&client, &alice);
+    assert_eq!(game(&client)["active_player"], "bob");
+
+    // Later suggestions follow it, in the order they were made.
+    move_to(&client, &bob, "Kitchen");
+    suggest(&client, &bob, ["Mustard", "Rope", "Kitchen"]);
+    let (_, history) = send(client.get("/game/history"));
+    assert_eq!(history[0]["no_suggestion_by"], "alice");
+    assert_eq!(history[1]["suggested_by"], "bob");
+}
+
+/// Serializes `value` only once let go, like a serializer that takes its
+/// time.
+struct Gated<'a, T> {
+    value: T,
+    started: &'a Barrier,
+    release: &'a Barrier,
+}
+
+impl<T: Serialize> Serialize for Gated<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.started.wait();
+        self.release.wait();
+        self.value.serialize(serializer)
+    }
+}
+
+#[test]
+fn writes_go_ahead_while_a_read_serializes() {
+    let client = client();
+    let [alice, ..] = seated(&client, &THREE_HANDS, json!({}));
+    let shared = client.rocket().state::<SharedGame>().unwrap();
+    let (started, release) = (&Barrier::new(2), &Barrier::new(2));
+
+    thread::scope(|scope| {
+        // Read the way the view handlers do: copy under the lock, then
+        // serialize the copy.
+        let (copy, version) = shared.snapshot().unwrap();
+        let read = scope.spawn(move || {
+            let view = copy.view_for(None, false);
+            to_string(&Gated {
+                value: &view,
+                started,
+                release,
+            })
+            .unwrap()
+        });
+
+        started.wait();
+        let (status, body) = send(client.post("/game/pause").header(bearer(&alice)));
+        let written = shared.version() > version;
+        release.wait();
+        assert_eq!(status, Status::NoContent, "{body}");
+        assert!(written);
+
+        let read: Value = serde_json::from_str(&read.join().unwrap()).unwrap();
+        assert_eq!(read["paused"], false);
+    });
+    assert_eq!(game(&client)["paused"], true);
+}
+
+#[test]
+fn suggested_suspects_are_called_into_the_room() {
+    let client = client();
+    let [alice, bob, _] = seated(&client, &THREE_HANDS, json!({}));
+
+    let location = |suspect: &str| {
+        let (_, tokens) = send(client.get("/game/suspect-tokens"));
+        tokens
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|token| token["suspect"] == suspect)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        location("Orchid"),
+        json!({ "suspect": "Orchid", "player": null, "location": "OrchidStart" })
+    );
+
+    move_to(&client, &alice, "Kitchen");
+    suggest(&client, &alice, ["Orchid", "Rope", "Kitchen"]);
+    assert_eq!(location("Orchid")["location"], "Kitchen");
+    end_turn(&client, &alice);
+
+    // Carol plays Mustard, so her own token is moved.
+    move_to(&client, &bob, "Lounge");
+    suggest(&client, &bob, ["Mustard", "Rope", "Lounge"]);
+    assert_eq!(
+        location("Mustard"),
+        json!({ "suspect": "Mustard", "player": "carol", "location": "Lounge" })
+    );
+}
+
+#[test]
+fn names_must_survive_the_path() {
+    let client = client();
+
+    for (encoded, reason) in [
+        ("a%2Fb", "name contains a path separator"),
+        ("100%25", "name contains a character with a meaning in URLs"),
+        ("%3F", "name contains a character with a meaning in URLs"),
+        ("%20", "name is empty"),
+        (".", "name is a path step"),
+    ] {
+        let (status, body) = send(client.post(format!("/players/{encoded}")));
+        assert_eq!(status, Status::UnprocessableEntity, "{encoded}");
+        assert_eq!(body["details"]["reason"], reason, "{encoded}");
+    }
+
+    join(&client, "Mary%20Jane");
+    assert_eq!(
+        send(client.get("/players/Mary%20Jane")).1["name"],
+        "Mary Jane"
+    );
+}
+
+#[test]
+fn finished_game_is_kept_read_only_then_cleared() {
+    let client = client();
+    let [alice, bob] = seated(
+        &client,
+        &TWO_HANDS,
+        json!({ "finished_retention_secs": 60 }),
+    );
+    accuse(&client, &alice, SOLUTION);
+
+    let response = client
+        .post("/game/rematch")
+        .header(bearer(&alice))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.headers().get_one("Retry-After"), Some("60"));
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["error"], "finished game is kept for review");
+    assert_eq!(body["details"]["seconds_left"], 60);
+
+    for (status, _) in [
+        send(client.delete("/game").header(bearer(&alice))),
+        send(client.delete("/players/bob").header(bearer(&bob))),
+        send(client.post("/game/kick/bob").header(bearer(&alice))),
+    ] {
+        assert_eq!(status, Status::Conflict);
+    }
+    assert_eq!(game(&client)["winner"], "alice");
+
+    with_game(&client, |state| state.finished_at -= 60);
+    let state = game(&client);
+    assert_eq!(state["phase"], "Lobby");
+    assert_eq!(state["players"], json!([]));
+    assert!(audit_actions(&client).contains(&"finished game cleared".to_owned()));
+    assert_eq!(send(client.get("/stats")).1["games_played"], 1);
+}
+
+#[test]
+fn candidate_counts_per_player() {
+    let client = client();
+    let [alice, _] = seated(&client, &TWO_HANDS, json!({}));
+
+    let (status, _) = send(client.get("/game/analytics/candidates"));
+    assert_eq!(status, Status::Unauthorized);
+
+    let (_, counts) = send(client.get("/game/analytics/candidates").header(admin()));
+    assert_eq!(
+        counts,
+        json!([
+            { "player": "alice", "suspect": 3, "weapon": 3, "room": 6 },
+            { "player": "bob", "suspect": 4, "weapon": 4, "room": 4 },
+        ])
+    );
+
+    // Bob can't disprove the envelope itself, which pins it for alice.
+    move_to(&client, &alice, "Study");
+    suggest(&client, &alice, SOLUTION);
+    let (_, counts) = send(client.get("/game/analytics/candidates").header(admin()));
+    assert_eq!(
+        counts[0],
+        json!({ "player": "alice", "suspect": 1, "weapon": 1, "room": 1 })
+    );
+    assert_eq!(counts[1]["suspect"], 4);
+}
+
+#[test]
+fn deleting_an_edition_leaves_running_games_alone() {
+    let client = client();
+    let edition = json!({
+        "name": "mini",
+        "deck": { "suspects": ["Plum", "Green"], "weapons": ["Rope", "Wrench"], "rooms": ["Hall", "Study"] },
+    });
+    send(client.post("/editions").header(admin()).json(&edition));
+
+    let alice = join(&client, "alice");
+    join(&client, "bob");
+    start(&client, &alice, json!({ "edition": "mini" }));
+
+    let (status, _) = send(client.delete("/editions/mini"));
+    assert_eq!(status, Status::Unauthorized);
+    let (status, _) = send(client.delete("/editions/mini").header(admin()));
+    assert_eq!(status, Status::NoContent);
+
+    let (_, config) = send(client.get("/game/config"));
+    assert_eq!(config["deck"]["weapons"], json!(["Rope", "Wrench"]));
+    assert_eq!(
+        config["secret_passages"],
+        json!([["Kitchen", "Study"], ["Lounge", "Conservatory"]])
+    );
+
+    let (_, report) = send(
+        client
+            .post("/game/validate-config")
+            .json(&json!({ "edition": "mini" })),
+    );
+    assert_eq!(report["errors"][0]["error"], "unknown edition");
+
+    let (status, body) = send(client.delete("/editions/mini").header(admin()));
+    assert_eq!(status, Status::NotFound);
+    assert_eq!(body["details"]["edition"], "mini");
+}